@@ -0,0 +1,45 @@
+use calc_dlmm::price;
+use calc_dlmm::swap_result::{DlmmPair, get_swap_in, get_swap_out, test_pair};
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+
+const BIN_STEP: u16 = 25;
+
+// 201 bins of 1_000_000 X and Y around real id 0, the swaps below cross ~50 of them
+fn pair() -> DlmmPair {
+    test_pair(BIN_STEP, 100)
+}
+
+fn swap(c: &mut Criterion) {
+    let plain = pair();
+    let mut cached = pair();
+    cached.enable_price_table(100);
+
+    for (name, pair) in [("pow", &plain), ("price_table", &cached)] {
+        c.bench_function(&format!("get_swap_out/{name}"), |b| {
+            b.iter(|| get_swap_out(pair, black_box(50_000_000), true, 0))
+        });
+        c.bench_function(&format!("get_swap_in/{name}"), |b| {
+            b.iter(|| get_swap_in(pair, black_box(50_000_000), false, 0))
+        });
+    }
+}
+
+fn bin_price(c: &mut Criterion) {
+    let storage_id = price::get_storage_id_from_real_id(-12_345);
+    c.bench_function("get_price_x128_from_storage_id", |b| {
+        b.iter(|| price::get_price_x128_from_storage_id(black_box(storage_id), BIN_STEP))
+    });
+}
+
+fn real_id_from_price(c: &mut Criterion) {
+    let price = price::get_price_x128_from_storage_id(
+        price::get_storage_id_from_real_id(-12_345),
+        BIN_STEP,
+    );
+    c.bench_function("get_real_id_from_price_x128", |b| {
+        b.iter(|| price::get_real_id_from_price_x128(black_box(price.to_string()), BIN_STEP))
+    });
+}
+
+criterion_group!(benches, swap, bin_price, real_id_from_price);
+criterion_main!(benches);
@@ -0,0 +1,284 @@
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    constants, price,
+    swap_result::{DlmmPair, DlmmPairParameter},
+    units::{Fee1e9, int_string, u64_string},
+};
+
+// Fee APR of a candidate position before deposit. The position value is spread over its
+// bins by the strategy weights of src/math/almmStrategy.ts, the period volume is spread
+// evenly over the bins the price moved through, and each bin pays the LP fee to the
+// position pro rata of its part of the bin value. Values are in raw Y units at the bin
+// prices; the pair reserves are taken as they are, swaps don't move them.
+
+const DEFAULT_MAX_WEIGHT: u64 = 2_000_000_000_000;
+const DEFAULT_MIN_WEIGHT: u64 = 200_000_000_000;
+const YEAR_MS: u64 = 365 * constants::DAY * 1000;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyType {
+    // same weight in every bin
+    Spot = 1,
+    // highest weight in the active bin, decreasing linearly to the range bounds
+    Curve = 2,
+    // lowest weight in the active bin, increasing linearly to the range bounds
+    BidAsk = 3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AprInput {
+    pub lower_real_id: i32,
+    pub upper_real_id: i32,
+    pub strategy: StrategyType,
+    // value of the deposit in Y
+    #[serde(with = "int_string")]
+    pub deposit_value_y: u128,
+    // volume in Y over the period, fee included
+    #[serde(with = "int_string")]
+    pub volume_y: u128,
+    #[serde(with = "u64_string")]
+    pub period_ms: u64,
+    // bins the price moved through on each side of the active one over the period
+    pub price_range_bins: u32,
+    // average volatility accumulator the swaps paid, basis points as on chain
+    pub volatility_accumulator: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AprEstimate {
+    // base fee + variable fee at the volatility accumulator, 1e9 precision
+    #[serde(with = "u64_string")]
+    pub fee_rate: u64,
+    // protocol share excluded, 1e9 precision
+    #[serde(with = "u64_string")]
+    pub lp_fee_rate: u64,
+    #[serde(with = "int_string")]
+    pub fees_y: u128,
+    // annualised fees_y / deposit_value_y, 1e9 precision
+    #[serde(with = "int_string")]
+    pub apr: u128,
+    pub bins: Vec<AprBin>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AprBin {
+    pub real_id: i32,
+    #[serde(with = "int_string")]
+    pub value_y: u128,
+    // part of the bin value the position holds, 1e9 precision
+    #[serde(with = "u64_string")]
+    pub share: u64,
+    #[serde(with = "int_string")]
+    pub volume_y: u128,
+    #[serde(with = "int_string")]
+    pub fees_y: u128,
+}
+
+// Weights of the bins lower_real_id..=upper_real_id, as toWeightSpotBalanced, toWeightCurve
+// and toWeightBidAsk
+pub fn get_strategy_weights(
+    strategy: StrategyType,
+    lower_real_id: i32,
+    upper_real_id: i32,
+    active_real_id: i32,
+) -> Vec<u64> {
+    assert!(lower_real_id <= upper_real_id, "ErrInvalidBinRange");
+    if strategy == StrategyType::Spot {
+        return vec![1; (upper_real_id - lower_real_id) as usize + 1];
+    }
+    assert!(
+        lower_real_id <= active_real_id && active_real_id <= upper_real_id,
+        "ErrInvalidStrategyParams"
+    );
+    let diff_weight = DEFAULT_MAX_WEIGHT - DEFAULT_MIN_WEIGHT;
+    let step = |bins: i32| {
+        if bins > 0 {
+            diff_weight / bins as u64
+        } else {
+            0
+        }
+    };
+    let diff_min_weight = step(active_real_id - lower_real_id);
+    let diff_max_weight = step(upper_real_id - active_real_id);
+    (lower_real_id..=upper_real_id)
+        .map(|id| {
+            let delta = if id < active_real_id {
+                (active_real_id - id) as u64 * diff_min_weight
+            } else {
+                (id - active_real_id) as u64 * diff_max_weight
+            };
+            match strategy {
+                StrategyType::Curve => DEFAULT_MAX_WEIGHT - delta,
+                _ => DEFAULT_MIN_WEIGHT + delta,
+            }
+        })
+        .collect()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    // AprInput as JSON, return the AprEstimate as JSON
+    #[cfg(feature = "wasm")]
+    pub fn estimate_apr_json(&self, json: &str) -> Result<String, JsError> {
+        let input: AprInput = serde_json::from_str(json)?;
+        Ok(serde_json::to_string(&self.estimate_apr(&input))?)
+    }
+}
+
+impl DlmmPair {
+    pub fn estimate_apr(&self, input: &AprInput) -> AprEstimate {
+        assert!(
+            input.deposit_value_y > 0 && input.period_ms > 0,
+            "ErrInvalidAprParams"
+        );
+        let active_real_id = price::get_real_id(self.active_id());
+        let weights = get_strategy_weights(
+            input.strategy,
+            input.lower_real_id,
+            input.upper_real_id,
+            active_real_id,
+        );
+        let total_weight = U256::from(weights.iter().map(|&w| w as u128).sum::<u128>());
+
+        let fee_rate = estimated_fee(self.params(), self.bin_step(), input);
+        let protocol_fee = fee_rate.mul_bps(self.params().protocol_share);
+        let lp_fee_rate = fee_rate.checked_sub(protocol_fee).unwrap();
+
+        let volume_per_bin = input.volume_y / (2 * input.price_range_bins as u128 + 1);
+        let bins: Vec<AprBin> = (input.lower_real_id..=input.upper_real_id)
+            .zip(&weights)
+            .map(|(real_id, &weight)| {
+                let value_y = (U256::from(input.deposit_value_y) * U256::from(weight)
+                    / total_weight)
+                    .to::<u128>();
+                let storage_id = price::get_storage_id_from_real_id(real_id);
+                let bin_value_y = self.bin(storage_id).map_or(U256::ZERO, |bin| {
+                    price::get_price_x128_from_storage_id(storage_id, self.bin_step())
+                        .x_to_y(bin.reserve_x)
+                        + U256::from(bin.reserve_y)
+                });
+                let total_value_y = bin_value_y + U256::from(value_y);
+                let share = if value_y == 0 {
+                    0
+                } else {
+                    (U256::from(value_y) * U256::from(constants::PRECISION) / total_value_y)
+                        .to::<u64>()
+                };
+                let volume_y = if real_id.abs_diff(active_real_id) <= input.price_range_bins {
+                    volume_per_bin
+                } else {
+                    0
+                };
+                let fees_y = if value_y == 0 {
+                    0
+                } else {
+                    (U256::from(volume_y) * U256::from(lp_fee_rate.get()) * U256::from(value_y)
+                        / (total_value_y * U256::from(constants::PRECISION)))
+                    .to::<u128>()
+                };
+                AprBin {
+                    real_id,
+                    value_y,
+                    share,
+                    volume_y,
+                    fees_y,
+                }
+            })
+            .collect();
+
+        let fees_y: u128 = bins.iter().map(|bin| bin.fees_y).sum();
+        let apr = U256::from(fees_y) * U256::from(constants::PRECISION) * U256::from(YEAR_MS)
+            / (U256::from(input.deposit_value_y) * U256::from(input.period_ms));
+        AprEstimate {
+            fee_rate: fee_rate.get(),
+            lp_fee_rate: lp_fee_rate.get(),
+            fees_y,
+            apr: apr.saturating_to::<u128>(),
+            bins,
+        }
+    }
+}
+
+// Total fee the swaps pay at the given volatility accumulator, capped at MAX_FEE as the
+// swaps would abort above it
+fn estimated_fee(params: &DlmmPairParameter, bin_step: u16, input: &AprInput) -> Fee1e9 {
+    let fee = params
+        .get_base_fee(bin_step)
+        .saturating_add(params.get_variable_fee_at(bin_step, input.volatility_accumulator));
+    fee.min(Fee1e9::MAX)
+}
+
+#[test]
+fn test_strategy_weights() {
+    assert!(get_strategy_weights(StrategyType::Spot, -2, 1, 5) == vec![1; 4]);
+    let curve = get_strategy_weights(StrategyType::Curve, -2, 4, 0);
+    assert!(curve[2] == DEFAULT_MAX_WEIGHT);
+    assert!(curve[0] == DEFAULT_MIN_WEIGHT && curve[6] == DEFAULT_MIN_WEIGHT);
+    assert!(curve[1] == 1_100_000_000_000 && curve[3] == 1_550_000_000_000);
+    let bid_ask = get_strategy_weights(StrategyType::BidAsk, 0, 2, 0);
+    assert!(bid_ask == vec![DEFAULT_MIN_WEIGHT, 1_100_000_000_000, DEFAULT_MAX_WEIGHT]);
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidStrategyParams")]
+fn test_strategy_weights_active_out_of_range() {
+    get_strategy_weights(StrategyType::Curve, 1, 4, 0);
+}
+
+#[test]
+fn test_estimate_apr() {
+    // 2_000_000 of value in every bin at the price 1
+    let pair = crate::swap_result::test_pair(25, 50);
+    let input = AprInput {
+        lower_real_id: -10,
+        upper_real_id: 10,
+        strategy: StrategyType::Spot,
+        deposit_value_y: 21_000_000,
+        volume_y: 1_100_000_000,
+        period_ms: constants::DAY * 1000,
+        price_range_bins: 5,
+        volatility_accumulator: 0,
+    };
+    let spot = pair.estimate_apr(&input);
+    // base fee 10000 * 25, protocol share 10%
+    assert!(spot.fee_rate == 250_000 && spot.lp_fee_rate == 225_000);
+    assert!(spot.bins.len() == 21 && spot.bins[10].value_y == 1_000_000);
+    // a third of each bin, 100_000_000 of volume in the 11 bins around the active one
+    assert!(spot.bins[10].share == 333_333_333 && spot.bins[10].volume_y == 100_000_000);
+    assert!(spot.bins[10].fees_y == 7500 && spot.bins[0].fees_y == 0);
+    // the other bins hold a bit more or less value in Y away from the price 1
+    assert!(spot.fees_y.abs_diff(11 * 7500) < 100);
+    assert!(spot.apr == spot.fees_y * 365 * constants::PRECISION / 21_000_000);
+
+    // the curve concentrates the deposit where the volume trades
+    let curve = pair.estimate_apr(&AprInput {
+        strategy: StrategyType::Curve,
+        ..input
+    });
+    let bid_ask = pair.estimate_apr(&AprInput {
+        strategy: StrategyType::BidAsk,
+        ..input
+    });
+    assert!(curve.apr > spot.apr && spot.apr > bid_ask.apr);
+
+    // volatility raises the fee
+    let volatile = pair.estimate_apr(&AprInput {
+        volatility_accumulator: 50_000,
+        ..input
+    });
+    assert!(volatile.fee_rate > spot.fee_rate && volatile.apr > spot.apr);
+
+    // the bins are valued at the price of their id, not the one they hold
+    let mut unpriced = pair.clone();
+    for bin in unpriced.bins.values_mut() {
+        bin.price_q128 = crate::units::PriceX128::ZERO;
+    }
+    assert!(unpriced.estimate_apr(&input) == spot);
+}
@@ -0,0 +1,425 @@
+use alloy_primitives::{U256, U512};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    constants, price,
+    swap_result::DlmmPair,
+    units::{PriceX128, int_string, u32_number, u64_string},
+};
+
+// Replays a swap series against a copy of a pair, with the dynamic fee and the volatility
+// accumulator updated as on chain, and follows LP positions holding fixed shares of bins.
+// Liquidity is not added or removed during the replay, so the shares don't change.
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backtest {
+    pub positions: Vec<Position>,
+    // by timestamp
+    pub swaps: Vec<BacktestSwap>,
+    // the position composition is recorded at most once per interval, 0 records it after
+    // every swap
+    #[serde(default, with = "u64_string")]
+    pub snapshot_interval_ms: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub bins: Vec<PositionBin>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionBin {
+    #[serde(with = "u32_number")]
+    pub storage_id: u32,
+    // part of the bin reserves and fees owned, 1e9 precision
+    #[serde(with = "u64_string")]
+    pub share: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacktestSwap {
+    #[serde(with = "u64_string")]
+    pub timestamp_ms: u64,
+    #[serde(with = "u64_string")]
+    pub amount_in: u64,
+    pub swap_for_y: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub positions: Vec<PositionReport>,
+    // swaps the pair could not fill completely
+    pub partial_swaps: u32,
+    #[serde(with = "int_string")]
+    pub volume_x: u128,
+    #[serde(with = "int_string")]
+    pub volume_y: u128,
+    #[serde(with = "u32_number")]
+    pub final_active_id: u32,
+    // the price the values are taken at
+    pub final_price_x128: PriceX128,
+}
+
+// Amounts in raw token units, values in Y at the final active bin price
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionReport {
+    #[serde(with = "int_string")]
+    pub initial_x: u128,
+    #[serde(with = "int_string")]
+    pub initial_y: u128,
+    #[serde(with = "int_string")]
+    pub final_x: u128,
+    #[serde(with = "int_string")]
+    pub final_y: u128,
+    // LP share of the fees, protocol fee excluded
+    #[serde(with = "int_string")]
+    pub fees_x: u128,
+    #[serde(with = "int_string")]
+    pub fees_y: u128,
+    // the initial amounts held instead
+    #[serde(with = "int_string")]
+    pub hodl_value_y: u128,
+    // fees included
+    #[serde(with = "int_string")]
+    pub final_value_y: u128,
+    // final_value_y - hodl_value_y, saturating
+    #[serde(with = "int_string")]
+    pub pnl_y: i128,
+    pub composition: Vec<CompositionPoint>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompositionPoint {
+    #[serde(with = "u64_string")]
+    pub timestamp_ms: u64,
+    #[serde(with = "int_string")]
+    pub amount_x: u128,
+    #[serde(with = "int_string")]
+    pub amount_y: u128,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    // Backtest as JSON, return the BacktestReport as JSON
+    #[cfg(feature = "wasm")]
+    pub fn backtest_json(&self, json: &str) -> Result<String, JsError> {
+        let backtest: Backtest = serde_json::from_str(json)?;
+        Ok(serde_json::to_string(&backtest.run(self))?)
+    }
+}
+
+impl Backtest {
+    // The pair is left unchanged
+    pub fn run(&self, pair: &DlmmPair) -> BacktestReport {
+        assert!(
+            self.swaps
+                .windows(2)
+                .all(|w| w[0].timestamp_ms <= w[1].timestamp_ms),
+            "ErrUnsortedSwaps"
+        );
+        let mut pair = pair.clone();
+        let shares: Vec<HashMap<u32, u64>> = self
+            .positions
+            .iter()
+            .map(|position| {
+                position
+                    .bins
+                    .iter()
+                    .map(|bin| {
+                        assert!(bin.share as u128 <= constants::PRECISION, "ErrInvalidShare");
+                        assert!(pair.bin(bin.storage_id).is_some(), "ErrBinNotFound");
+                        (bin.storage_id, bin.share)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let start_ms = self.swaps.first().map_or(0, |swap| swap.timestamp_ms);
+        let initial: Vec<(u128, u128)> = shares
+            .iter()
+            .map(|shares| position_amounts(&pair, shares))
+            .collect();
+        let mut reports: Vec<PositionReport> = initial
+            .iter()
+            .map(|&(amount_x, amount_y)| PositionReport {
+                initial_x: amount_x,
+                initial_y: amount_y,
+                final_x: amount_x,
+                final_y: amount_y,
+                fees_x: 0,
+                fees_y: 0,
+                hodl_value_y: 0,
+                final_value_y: 0,
+                pnl_y: 0,
+                composition: vec![CompositionPoint {
+                    timestamp_ms: start_ms,
+                    amount_x,
+                    amount_y,
+                }],
+            })
+            .collect();
+
+        let mut partial_swaps = 0;
+        let (mut volume_x, mut volume_y) = (0u128, 0u128);
+        let mut last_snapshot_ms = start_ms;
+        for swap in &self.swaps {
+            let (result, event) =
+                pair.simulate_swap_out(swap.amount_in, swap.swap_for_y, swap.timestamp_ms);
+            pair.apply_swap(&event);
+            if result.amount_in_left > 0 {
+                partial_swaps += 1;
+            };
+            let amount_in = (swap.amount_in - result.amount_in_left) as u128;
+            if swap.swap_for_y {
+                volume_x += amount_in;
+            } else {
+                volume_y += amount_in;
+            };
+
+            for step in &event.bins {
+                let lp_fee = (step.fee - step.protocol_fee) as u128;
+                for (report, shares) in reports.iter_mut().zip(&shares) {
+                    let Some(&share) = shares.get(&step.storage_id) else {
+                        continue;
+                    };
+                    let fee = lp_fee * share as u128 / constants::PRECISION;
+                    if swap.swap_for_y {
+                        report.fees_x += fee;
+                    } else {
+                        report.fees_y += fee;
+                    };
+                }
+            }
+
+            if swap.timestamp_ms >= last_snapshot_ms.saturating_add(self.snapshot_interval_ms) {
+                last_snapshot_ms = swap.timestamp_ms;
+                for (report, shares) in reports.iter_mut().zip(&shares) {
+                    let (amount_x, amount_y) = position_amounts(&pair, shares);
+                    report.composition.push(CompositionPoint {
+                        timestamp_ms: swap.timestamp_ms,
+                        amount_x,
+                        amount_y,
+                    });
+                }
+            };
+        }
+
+        let final_price_x128 =
+            price::get_price_x128_from_storage_id(pair.active_id(), pair.bin_step());
+        for (report, shares) in reports.iter_mut().zip(&shares) {
+            (report.final_x, report.final_y) = position_amounts(&pair, shares);
+            report.hodl_value_y = value_in_y(report.initial_x, report.initial_y, final_price_x128);
+            report.final_value_y = value_in_y(
+                report.final_x.saturating_add(report.fees_x),
+                report.final_y.saturating_add(report.fees_y),
+                final_price_x128,
+            );
+            report.pnl_y = pnl(report.final_value_y, report.hodl_value_y);
+        }
+
+        BacktestReport {
+            positions: reports,
+            partial_swaps,
+            volume_x,
+            volume_y,
+            final_active_id: pair.active_id(),
+            final_price_x128,
+        }
+    }
+}
+
+// Rounded down in each bin
+fn position_amounts(pair: &DlmmPair, shares: &HashMap<u32, u64>) -> (u128, u128) {
+    let (mut amount_x, mut amount_y) = (0u128, 0u128);
+    for (&storage_id, &share) in shares {
+        let bin = pair.bin(storage_id).expect("ErrBinNotFound");
+        amount_x += bin.reserve_x as u128 * share as u128 / constants::PRECISION;
+        amount_y += bin.reserve_y as u128 * share as u128 / constants::PRECISION;
+    }
+    (amount_x, amount_y)
+}
+
+// Saturating, the value of u128 amounts at a 2^224 price can exceed u128
+fn value_in_y(amount_x: u128, amount_y: u128, price: PriceX128) -> u128 {
+    let value_x = (U512::from(amount_x) * U512::from(price.x128())) >> constants::SCALE_OFFSET;
+    let value = value_x + U512::from(amount_y);
+    u128::try_from(U256::from(value.min(U512::from(u128::MAX)))).unwrap()
+}
+
+// Saturating at the i128 bounds
+fn pnl(final_value_y: u128, hodl_value_y: u128) -> i128 {
+    match final_value_y.checked_sub(hodl_value_y) {
+        Some(gain) => i128::try_from(gain).unwrap_or(i128::MAX),
+        None => i128::try_from(hodl_value_y - final_value_y).map_or(i128::MIN, |loss| -loss),
+    }
+}
+
+#[cfg(test)]
+fn full_share(real_ids: std::ops::RangeInclusive<i32>) -> Position {
+    Position {
+        bins: real_ids
+            .map(|real_id| PositionBin {
+                storage_id: price::get_storage_id_from_real_id(real_id),
+                share: constants::PRECISION as u64,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_backtest_round_trip() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 20);
+    let backtest = Backtest {
+        positions: vec![full_share(-20..=20), full_share(5..=20)],
+        swaps: vec![
+            BacktestSwap {
+                timestamp_ms: 1_000_000,
+                amount_in: 3_000_000,
+                swap_for_y: true,
+            },
+            BacktestSwap {
+                timestamp_ms: 1_001_000,
+                amount_in: 3_000_000,
+                swap_for_y: false,
+            },
+        ],
+        snapshot_interval_ms: 0,
+    };
+    let report = backtest.run(&pair);
+    assert!(report.partial_swaps == 0);
+    assert!(report.volume_x == 3_000_000 && report.volume_y == 3_000_000);
+
+    // the whole pair: every LP fee and the reserves of every bin
+    let all = &report.positions[0];
+    assert!(all.initial_x == 21_000_000 && all.initial_y == 21_000_000);
+    assert!(all.composition.len() == 3);
+    assert!(all.composition[1].amount_x > all.initial_x);
+    assert!(all.composition[1].amount_y < all.initial_y);
+    let mut replayed = pair.clone();
+    let mut protocol_fees = (0u128, 0u128);
+    for swap in &backtest.swaps {
+        let (_, event) =
+            replayed.simulate_swap_out(swap.amount_in, swap.swap_for_y, swap.timestamp_ms);
+        replayed.apply_swap(&event);
+        let protocol_fee: u128 = event
+            .bins
+            .iter()
+            .map(|step| step.protocol_fee as u128)
+            .sum();
+        if swap.swap_for_y {
+            protocol_fees.0 += protocol_fee;
+        } else {
+            protocol_fees.1 += protocol_fee;
+        };
+    }
+    let fee_x: u128 = replayed.bins().map(|bin| bin.fee_x as u128).sum();
+    let fee_y: u128 = replayed.bins().map(|bin| bin.fee_y as u128).sum();
+    assert!(all.fees_x == fee_x && all.fees_y == fee_y && fee_x > 0 && fee_y > 0);
+    assert!(protocol_fees.0 > 0 && protocol_fees.1 > 0);
+    assert!(report.final_active_id == replayed.active_id());
+    // the pair gains the fees it kept, protocol fees aside
+    let total_x: u128 = replayed.bins().map(|bin| bin.reserve_x as u128).sum();
+    assert!(all.final_x == total_x);
+    assert!(all.pnl_y == all.final_value_y as i128 - all.hodl_value_y as i128);
+
+    // bins 5..=20 are above the price the swaps reached, untouched
+    let far = &report.positions[1];
+    assert!(far.fees_x == 0 && far.fees_y == 0);
+    assert!(far.final_x == far.initial_x && far.initial_x == 16_000_000);
+    assert!(far.pnl_y == 0);
+}
+
+#[test]
+fn test_backtest_fee_share_and_snapshots() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 20);
+    let active_id = pair.active_id();
+    let half = Position {
+        bins: vec![PositionBin {
+            storage_id: active_id,
+            share: constants::PRECISION as u64 / 2,
+        }],
+    };
+    let full = Position {
+        bins: vec![PositionBin {
+            storage_id: active_id,
+            share: constants::PRECISION as u64,
+        }],
+    };
+    // small swaps staying in the active bin, a snapshot every 10s
+    let swaps = (0..10)
+        .map(|i| BacktestSwap {
+            timestamp_ms: 1_000_000 + i * 5_000,
+            amount_in: 10_000,
+            swap_for_y: i % 2 == 0,
+        })
+        .collect();
+    let backtest = Backtest {
+        positions: vec![half, full],
+        swaps,
+        snapshot_interval_ms: 10_000,
+    };
+    let report = backtest.run(&pair);
+    let (half, full) = (&report.positions[0], &report.positions[1]);
+    assert!(full.fees_x > 0 && full.fees_y > 0);
+    assert!(
+        half.fees_x.abs_diff(full.fees_x / 2) <= 5 && half.fees_y.abs_diff(full.fees_y / 2) <= 5
+    );
+    // the start, then swaps 2, 4, 6 and 8
+    let times: Vec<u64> = full
+        .composition
+        .iter()
+        .map(|point| point.timestamp_ms)
+        .collect();
+    assert!(times == [1_000_000, 1_010_000, 1_020_000, 1_030_000, 1_040_000]);
+    assert!(report.final_active_id == active_id);
+    // the fees earned in the active bin beat holding
+    assert!(full.pnl_y > 0);
+
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(json.contains("\"pnl_y\":\""));
+    assert!(serde_json::from_str::<BacktestReport>(&json).unwrap() == report);
+}
+
+#[test]
+fn test_backtest_partial_swap() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 2);
+    let backtest = Backtest {
+        positions: vec![],
+        swaps: vec![BacktestSwap {
+            timestamp_ms: 0,
+            amount_in: 100_000_000,
+            swap_for_y: true,
+        }],
+        snapshot_interval_ms: 0,
+    };
+    let report = backtest.run(&pair);
+    assert!(report.partial_swaps == 1 && report.volume_x < 100_000_000);
+}
+
+#[test]
+#[should_panic(expected = "ErrUnsortedSwaps")]
+fn test_backtest_unsorted_swaps() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 2);
+    let swap = |timestamp_ms| BacktestSwap {
+        timestamp_ms,
+        amount_in: 1,
+        swap_for_y: true,
+    };
+    let backtest = Backtest {
+        positions: vec![],
+        swaps: vec![swap(2), swap(1)],
+        snapshot_interval_ms: 0,
+    };
+    backtest.run(&pair);
+}
+
+#[test]
+fn test_pnl_saturates() {
+    assert!(pnl(7, 5) == 2 && pnl(5, 7) == -2);
+    assert!(pnl(i128::MAX as u128, 0) == i128::MAX && pnl(0, i128::MAX as u128) == -i128::MAX);
+    assert!(pnl(u128::MAX, 0) == i128::MAX && pnl(0, u128::MAX) == i128::MIN);
+}
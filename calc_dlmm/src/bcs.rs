@@ -0,0 +1,290 @@
+use alloy_primitives::U256;
+use std::fmt;
+
+use crate::{
+    swap_result::{Bin, DlmmPairParameter},
+    units::PriceX128,
+};
+
+// Decoding of the Sui BCS bytes of the on-chain pair parameters and bins, e.g. the
+// devInspectTransactionBlock return values. Fields are read in the Move struct order.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BcsError {
+    // the bytes ended in the middle of a value
+    UnexpectedEnd,
+    // bytes left after the value
+    TrailingBytes,
+    // a vector length that is not a valid uleb128 u32
+    InvalidLength,
+}
+
+impl fmt::Display for BcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BcsError::UnexpectedEnd => "ErrBcsUnexpectedEnd",
+            BcsError::TrailingBytes => "ErrBcsTrailingBytes",
+            BcsError::InvalidLength => "ErrBcsInvalidLength",
+        })
+    }
+}
+
+impl std::error::Error for BcsError {}
+
+#[cfg(feature = "wasm")]
+impl From<BcsError> for wasm_bindgen::JsValue {
+    fn from(error: BcsError) -> Self {
+        wasm_bindgen::JsError::new(&error.to_string()).into()
+    }
+}
+
+// BCS of the Move `PairParameter` struct
+pub fn decode_pair_parameter(bytes: &[u8]) -> Result<DlmmPairParameter, BcsError> {
+    let mut reader = Reader::new(bytes);
+    let params = reader.pair_parameter()?;
+    reader.finish()?;
+    Ok(params)
+}
+
+// BCS of a Move `vector<Bin>`
+pub fn decode_bins(bytes: &[u8]) -> Result<Vec<Bin>, BcsError> {
+    let mut reader = Reader::new(bytes);
+    let len = reader.length()?;
+    let bins = (0..len)
+        .map(|_| reader.bin())
+        .collect::<Result<Vec<_>, _>>()?;
+    reader.finish()?;
+    Ok(bins)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn finish(&self) -> Result<(), BcsError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(BcsError::TrailingBytes)
+        }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BcsError> {
+        let (head, rest) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or(BcsError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    fn u8(&mut self) -> Result<u8, BcsError> {
+        self.take::<1>().map(|[byte]| byte)
+    }
+
+    fn u16(&mut self) -> Result<u16, BcsError> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, BcsError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, BcsError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn u256(&mut self) -> Result<U256, BcsError> {
+        self.take::<32>().map(U256::from_le_bytes)
+    }
+
+    // uleb128 vector length, at most u32::MAX as in BCS
+    fn length(&mut self) -> Result<usize, BcsError> {
+        let mut value = 0u64;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                // reject non-canonical encodings with trailing zero bytes
+                if (shift > 0 && byte == 0) || value > u32::MAX as u64 {
+                    return Err(BcsError::InvalidLength);
+                };
+                return Ok(value as usize);
+            };
+        }
+        Err(BcsError::InvalidLength)
+    }
+
+    fn pair_parameter(&mut self) -> Result<DlmmPairParameter, BcsError> {
+        Ok(DlmmPairParameter {
+            base_factor: self.u16()?,
+            filter_period: self.u16()?,
+            decay_period: self.u16()?,
+            reduction_factor: self.u16()?,
+            variable_fee_control: self.u32()?,
+            protocol_share: self.u16()?,
+            max_volatility_accumulator: self.u32()?,
+            volatility_accumulator: self.u32()?,
+            volatility_reference: self.u32()?,
+            index_reference: self.u32()?,
+            time_of_last_update: self.u64()?,
+            oracle_index: self.u16()?,
+            active_index: self.u32()?,
+        })
+    }
+
+    fn bin(&mut self) -> Result<Bin, BcsError> {
+        Ok(Bin {
+            storage_id: self.u32()?,
+            price_q128: PriceX128::from_x128(self.u256()?),
+            reserve_x: self.u64()?,
+            reserve_y: self.u64()?,
+            staked_liquidity: self.u256()?,
+            staked_lp_amount: self.u64()?,
+            fee_x: self.u64()?,
+            fee_y: self.u64()?,
+            fee_growth_x: self.u256()?,
+            fee_growth_y: self.u256()?,
+            rewarder_growth: {
+                let len = self.length()?;
+                (0..len).map(|_| self.u256()).collect::<Result<_, _>>()?
+            },
+            distribution_growth: self.u256()?,
+            distribution_last_updated: self.u64()?,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn encode_pair_parameter(params: &DlmmPairParameter) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend(params.base_factor.to_le_bytes());
+    out.extend(params.filter_period.to_le_bytes());
+    out.extend(params.decay_period.to_le_bytes());
+    out.extend(params.reduction_factor.to_le_bytes());
+    out.extend(params.variable_fee_control.to_le_bytes());
+    out.extend(params.protocol_share.to_le_bytes());
+    out.extend(params.max_volatility_accumulator.to_le_bytes());
+    out.extend(params.volatility_accumulator.to_le_bytes());
+    out.extend(params.volatility_reference.to_le_bytes());
+    out.extend(params.index_reference.to_le_bytes());
+    out.extend(params.time_of_last_update.to_le_bytes());
+    out.extend(params.oracle_index.to_le_bytes());
+    out.extend(params.active_index.to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+pub(crate) fn encode_bin(bin: &Bin, out: &mut Vec<u8>) {
+    out.extend(bin.storage_id.to_le_bytes());
+    out.extend(bin.price_q128.x128().to_le_bytes::<32>());
+    out.extend(bin.reserve_x.to_le_bytes());
+    out.extend(bin.reserve_y.to_le_bytes());
+    out.extend(bin.staked_liquidity.to_le_bytes::<32>());
+    out.extend(bin.staked_lp_amount.to_le_bytes());
+    out.extend(bin.fee_x.to_le_bytes());
+    out.extend(bin.fee_y.to_le_bytes());
+    out.extend(bin.fee_growth_x.to_le_bytes::<32>());
+    out.extend(bin.fee_growth_y.to_le_bytes::<32>());
+    out.push(bin.rewarder_growth.len() as u8);
+    for growth in &bin.rewarder_growth {
+        out.extend(growth.to_le_bytes::<32>());
+    }
+    out.extend(bin.distribution_growth.to_le_bytes::<32>());
+    out.extend(bin.distribution_last_updated.to_le_bytes());
+}
+
+#[test]
+fn test_decode_pair_parameter() {
+    // hand-encoded to pin the field order
+    let mut bytes = Vec::new();
+    bytes.extend(10000u16.to_le_bytes());
+    bytes.extend(30u16.to_le_bytes());
+    bytes.extend(600u16.to_le_bytes());
+    bytes.extend(5000u16.to_le_bytes());
+    bytes.extend(40000u32.to_le_bytes());
+    bytes.extend(1000u16.to_le_bytes());
+    bytes.extend(350000u32.to_le_bytes());
+    bytes.extend(1234u32.to_le_bytes());
+    bytes.extend(567u32.to_le_bytes());
+    bytes.extend(8388600u32.to_le_bytes());
+    bytes.extend(1_700_000_000u64.to_le_bytes());
+    bytes.extend(7u16.to_le_bytes());
+    bytes.extend(8388608u32.to_le_bytes());
+
+    let params = decode_pair_parameter(&bytes).unwrap();
+    assert!(
+        params
+            == DlmmPairParameter {
+                base_factor: 10000,
+                filter_period: 30,
+                decay_period: 600,
+                reduction_factor: 5000,
+                variable_fee_control: 40000,
+                protocol_share: 1000,
+                max_volatility_accumulator: 350000,
+                volatility_accumulator: 1234,
+                volatility_reference: 567,
+                index_reference: 8388600,
+                time_of_last_update: 1_700_000_000,
+                oracle_index: 7,
+                active_index: 8388608,
+            }
+    );
+    assert!(encode_pair_parameter(&params) == bytes);
+
+    assert!(decode_pair_parameter(&bytes[..bytes.len() - 1]) == Err(BcsError::UnexpectedEnd));
+    bytes.push(0);
+    assert!(decode_pair_parameter(&bytes) == Err(BcsError::TrailingBytes));
+}
+
+#[test]
+fn test_decode_bins() {
+    let bins = vec![
+        Bin {
+            storage_id: 8388607,
+            price_q128: crate::price::get_price_x128_from_storage_id(8388607, 25),
+            // above 2^53, where the JSON path loses precision in JS
+            reserve_y: (1 << 53) + 1,
+            fee_growth_y: U256::MAX,
+            ..Default::default()
+        },
+        Bin {
+            storage_id: 8388608,
+            price_q128: PriceX128::ONE,
+            reserve_x: u64::MAX,
+            reserve_y: 42,
+            staked_liquidity: U256::from(1) << 200,
+            staked_lp_amount: 3,
+            fee_x: 4,
+            fee_y: 5,
+            rewarder_growth: vec![U256::from(6), U256::from(7), U256::from(1) << 255],
+            distribution_growth: U256::from(8),
+            distribution_last_updated: 9,
+            ..Default::default()
+        },
+    ];
+    let mut bytes = vec![bins.len() as u8];
+    for bin in &bins {
+        encode_bin(bin, &mut bytes);
+    }
+    assert!(decode_bins(&bytes).unwrap() == bins);
+    assert!(decode_bins(&[0]).unwrap().is_empty());
+    assert!(decode_bins(&bytes[..bytes.len() - 8]) == Err(BcsError::UnexpectedEnd));
+}
+
+#[test]
+fn test_uleb128_length() {
+    let length = |bytes: &[u8]| Reader::new(bytes).length();
+    assert!(length(&[0x7f]) == Ok(127));
+    assert!(length(&[0x80, 0x01]) == Ok(128));
+    assert!(length(&[0xff, 0xff, 0xff, 0xff, 0x0f]) == Ok(u32::MAX as usize));
+    assert!(length(&[0x80, 0x80, 0x80, 0x80, 0x10]) == Err(BcsError::InvalidLength));
+    assert!(length(&[0x80, 0x00]) == Err(BcsError::InvalidLength));
+    assert!(length(&[0x80]) == Err(BcsError::UnexpectedEnd));
+}
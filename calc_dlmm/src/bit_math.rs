@@ -1,32 +1,4 @@
 use alloy_primitives::U256;
-use std::str::FromStr;
-
-fn u256_max() -> U256 {
-    U256::from_str("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap()
-}
-fn u128_max() -> U256 {
-    U256::from_str("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap()
-}
-
-fn u64_max() -> U256 {
-    U256::from_str("0xFFFFFFFFFFFFFFFF").unwrap()
-}
-
-fn u32_max() -> U256 {
-    U256::from_str("0xFFFFFFFF").unwrap()
-}
-
-fn u16_max() -> U256 {
-    U256::from_str("0xFFFF").unwrap()
-}
-
-fn u8_max() -> U256 {
-    U256::from_str("0xFF").unwrap()
-}
-
-fn u4_max() -> U256 {
-    U256::from_str("0xF").unwrap()
-}
 
 /**
  * @dev Returns the index of the most significant bit of x
@@ -34,39 +6,24 @@ fn u4_max() -> U256 {
  * @param x The value as a uint256
  * @return msb The index of the most significant bit of x
  */
-pub fn most_significant_bit(mut bits: U256) -> u8 {
-    let mut msb = 0;
-    if bits > u128_max() {
-        bits = bits >> 128;
-        msb = 128;
-    };
-    if bits > u64_max() {
-        bits = bits >> 64;
-        msb = msb + 64;
-    };
-    if bits > u32_max() {
-        bits = bits >> 32;
-        msb = msb + 32;
-    };
-    if bits > u16_max() {
-        bits = bits >> 16;
-        msb = msb + 16;
-    };
-    if bits > u8_max() {
-        bits = bits >> 8;
-        msb = msb + 8;
-    };
-    if bits > u4_max() {
-        bits = bits >> 4;
-        msb = msb + 4;
-    };
-    if bits > U256::from_str("0x3").unwrap() {
-        bits = bits >> 2;
-        msb = msb + 2;
-    };
-    if bits > U256::from_str("0x1").unwrap() {
-        msb = msb + 1;
-    };
-
-    return msb;
+pub fn most_significant_bit(bits: U256) -> u8 {
+    if bits == U256::ZERO {
+        return 0;
+    };
+
+    (255 - bits.leading_zeros()) as u8
+}
+
+#[test]
+fn test_most_significant_bit() {
+    assert!(most_significant_bit(U256::ZERO) == 0);
+    assert!(most_significant_bit(U256::from(1)) == 0);
+    assert!(most_significant_bit(U256::from(3)) == 1);
+    assert!(most_significant_bit(U256::from(u128::MAX)) == 127);
+    assert!(most_significant_bit(U256::MAX) == 255);
+    for bit in 0..256 {
+        let x = U256::from(1) << bit;
+        assert!(most_significant_bit(x) as usize == bit);
+        assert!(most_significant_bit(x | (x >> 1)) as usize == bit);
+    }
 }
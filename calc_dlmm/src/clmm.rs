@@ -0,0 +1,7 @@
+// Concentrated liquidity pools, ported from the TypeScript maths of the SDK and checked
+// against the Move contract. Sqrt prices are Q64.64 u128, strings in the wasm bindings.
+pub mod collect_fees;
+pub mod liquidity_math;
+pub mod pool;
+pub mod swap_math;
+pub mod tick_math;
@@ -0,0 +1,271 @@
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    clmm::pool::{ClmmPool, ClmmTick},
+    uint_safe::safe64,
+    units::{int_string, int_string_vec, u64_string},
+};
+
+// Port of collectFeesQuote (src/math/collect-fees.ts) extended to the rewarders, with the
+// wrapping u128 subtractions of the Move position and rewarder modules
+
+// Checkpoints of a position, the SDK's PositionReward with the rewarders as lists
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClmmPosition {
+    #[serde(default)]
+    pub pos_object_id: String,
+    #[serde(with = "int_string")]
+    pub liquidity: u128,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    #[serde(with = "int_string")]
+    pub fee_growth_inside_a: u128,
+    #[serde(with = "u64_string")]
+    pub fee_owed_a: u64,
+    #[serde(with = "int_string")]
+    pub fee_growth_inside_b: u128,
+    #[serde(with = "u64_string")]
+    pub fee_owed_b: u64,
+    #[serde(default, with = "int_string_vec")]
+    pub reward_growth_inside: Vec<u128>,
+    #[serde(default, with = "int_string_vec")]
+    pub reward_amount_owed: Vec<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectFeesQuote {
+    pub pos_object_id: String,
+    #[serde(with = "u64_string")]
+    pub fee_owed_a: u64,
+    #[serde(with = "u64_string")]
+    pub fee_owed_b: u64,
+    // one per rewarder of the pool
+    #[serde(with = "int_string_vec")]
+    pub reward_owed: Vec<u64>,
+    // the position checkpoints once updated to the pool state
+    #[serde(with = "int_string")]
+    pub fee_growth_inside_a: u128,
+    #[serde(with = "int_string")]
+    pub fee_growth_inside_b: u128,
+    #[serde(with = "int_string_vec")]
+    pub reward_growth_inside: Vec<u128>,
+}
+
+// Growths per unit of liquidity inside a tick range, Q64.64
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GrowthInside {
+    pub fee_a: u128,
+    pub fee_b: u128,
+    pub rewarders: Vec<u128>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ClmmPool {
+    // Positions as a JSON list of ClmmPosition, return the list of CollectFeesQuote
+    #[cfg(feature = "wasm")]
+    pub fn collect_fees_quote_json(&self, json: &str) -> Result<String, JsError> {
+        let positions: Vec<ClmmPosition> = serde_json::from_str(json)?;
+        Ok(serde_json::to_string(&self.collect_fees_quote(&positions))?)
+    }
+}
+
+impl ClmmPool {
+    // The ticks missing from the pool are uninitialized, with no growth outside
+    pub fn get_growth_inside(&self, tick_lower_index: i32, tick_upper_index: i32) -> GrowthInside {
+        assert!(tick_lower_index < tick_upper_index, "ErrInvalidTickRange");
+        let uninitialized = ClmmTick::default();
+        let lower = self.ticks.get(&tick_lower_index).unwrap_or(&uninitialized);
+        let upper = self.ticks.get(&tick_upper_index).unwrap_or(&uninitialized);
+        let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+            growth_inside(
+                self.current_tick_index,
+                (tick_lower_index, lower_outside),
+                (tick_upper_index, upper_outside),
+                global,
+            )
+        };
+
+        GrowthInside {
+            fee_a: inside(
+                self.fee_growth_global_a,
+                lower.fee_growth_outside_a,
+                upper.fee_growth_outside_a,
+            ),
+            fee_b: inside(
+                self.fee_growth_global_b,
+                lower.fee_growth_outside_b,
+                upper.fee_growth_outside_b,
+            ),
+            rewarders: self
+                .rewarder_growth_global
+                .iter()
+                .enumerate()
+                .map(|(i, &global)| {
+                    inside(
+                        global,
+                        nth_or_zero(&lower.rewarders_growth_outside, i),
+                        nth_or_zero(&upper.rewarders_growth_outside, i),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    // Fees and rewards owed to each position at the pool state. The rewarder growths are
+    // taken as given, settle them to the quote time first
+    pub fn collect_fees_quote(&self, positions: &[ClmmPosition]) -> Vec<CollectFeesQuote> {
+        positions
+            .iter()
+            .map(|position| {
+                let inside =
+                    self.get_growth_inside(position.tick_lower_index, position.tick_upper_index);
+                let owed = |owed: u64, inside: u128, checkpoint: u128| {
+                    update_owed(owed, position.liquidity, inside, checkpoint)
+                };
+                let reward_owed = inside
+                    .rewarders
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &growth)| {
+                        owed(
+                            nth_or_zero(&position.reward_amount_owed, i),
+                            growth,
+                            nth_or_zero(&position.reward_growth_inside, i),
+                        )
+                    })
+                    .collect();
+                CollectFeesQuote {
+                    pos_object_id: position.pos_object_id.clone(),
+                    fee_owed_a: owed(
+                        position.fee_owed_a,
+                        inside.fee_a,
+                        position.fee_growth_inside_a,
+                    ),
+                    fee_owed_b: owed(
+                        position.fee_owed_b,
+                        inside.fee_b,
+                        position.fee_growth_inside_b,
+                    ),
+                    reward_owed,
+                    fee_growth_inside_a: inside.fee_a,
+                    fee_growth_inside_b: inside.fee_b,
+                    reward_growth_inside: inside.rewarders,
+                }
+            })
+            .collect()
+    }
+}
+
+fn nth_or_zero<T: Copy + Default>(values: &[T], i: usize) -> T {
+    values.get(i).copied().unwrap_or_default()
+}
+
+// global - below - above, the outside growths being below the range for the lower tick and
+// above it for the upper one when the current tick is in the range
+fn growth_inside(
+    current_tick_index: i32,
+    (lower_index, lower_outside): (i32, u128),
+    (upper_index, upper_outside): (i32, u128),
+    global: u128,
+) -> u128 {
+    let below = if current_tick_index < lower_index {
+        global.wrapping_sub(lower_outside)
+    } else {
+        lower_outside
+    };
+    let above = if current_tick_index < upper_index {
+        upper_outside
+    } else {
+        global.wrapping_sub(upper_outside)
+    };
+    global.wrapping_sub(below).wrapping_sub(above)
+}
+
+// owed + liquidity * (inside - checkpoint) >> 64, aborting past u64 as Move does
+fn update_owed(owed: u64, liquidity: u128, inside: u128, checkpoint: u128) -> u64 {
+    let delta = (U256::from(liquidity) * U256::from(inside.wrapping_sub(checkpoint))) >> 64;
+    safe64(U256::from(owed) + delta)
+}
+
+#[test]
+fn test_growth_inside() {
+    let mut pool = crate::clmm::pool::test_pool();
+    pool.set_growth_global(10 << 64, 1 << 64, vec![4 << 64]);
+    for (index, fee_growth_outside_a, fee_growth_outside_b) in [
+        (-600, 2u128 << 64, 3u128 << 64),
+        (600, 3 << 64, 0),
+        (1200, 1 << 64, 0),
+    ] {
+        let tick = pool.ticks.get_mut(&index).unwrap();
+        tick.fee_growth_outside_a = fee_growth_outside_a;
+        tick.fee_growth_outside_b = fee_growth_outside_b;
+    }
+    // current tick 0 in range: 10 - 2 - 3
+    let inside = pool.get_growth_inside(-600, 600);
+    assert!(inside.fee_a == 5 << 64);
+    // 1 - 3 - 0 wraps around
+    assert!(inside.fee_b == 0u128.wrapping_sub(2 << 64));
+    // the ticks hold no rewarder growth outside
+    assert!(inside.rewarders == vec![4 << 64]);
+    // above the current tick: outside(600) - outside(1200)
+    assert!(pool.get_growth_inside(600, 1200).fee_a == 2 << 64);
+    // uninitialized ticks, the whole growth is inside when in range
+    assert!(pool.get_growth_inside(-60, 60).fee_a == 10 << 64);
+    assert!(pool.get_growth_inside(60, 120).fee_a == 0);
+}
+
+#[test]
+fn test_collect_fees_quote() {
+    // growth inside [-600, 600): 10 - 5 of A, 1 - 3 of B, 4 of the rewarder
+    let mut pool = crate::clmm::pool::test_pool();
+    pool.set_growth_global(10 << 64, 1 << 64, vec![4 << 64]);
+    let tick = pool.ticks.get_mut(&-600).unwrap();
+    tick.fee_growth_outside_a = 5 << 64;
+    tick.fee_growth_outside_b = 3 << 64;
+    let position = ClmmPosition {
+        pos_object_id: "0x1".to_string(),
+        liquidity: 1000,
+        tick_lower_index: -600,
+        tick_upper_index: 600,
+        fee_growth_inside_a: 1 << 64,
+        fee_owed_a: 7,
+        // checkpoint -4, the growth inside moved from -4 to -2
+        fee_growth_inside_b: 0u128.wrapping_sub(4 << 64),
+        fee_owed_b: 0,
+        ..Default::default()
+    };
+    let wrapped = ClmmPosition {
+        pos_object_id: "0x2".to_string(),
+        fee_growth_inside_a: 0u128.wrapping_sub(1 << 64),
+        reward_growth_inside: vec![3 << 64],
+        reward_amount_owed: vec![5],
+        ..position.clone()
+    };
+    let quotes = pool.collect_fees_quote(&[position, wrapped]);
+    assert!(quotes[0].fee_owed_a == 4007 && quotes[0].fee_owed_b == 2000);
+    assert!(quotes[0].reward_owed == vec![4000]);
+    assert!(quotes[0].fee_growth_inside_a == 5 << 64);
+    assert!(quotes[1].pos_object_id == "0x2");
+    assert!(quotes[1].fee_owed_a == 6007 && quotes[1].reward_owed == vec![1005]);
+
+    let json = serde_json::to_string(&quotes[1]).unwrap();
+    assert!(json.contains("\"reward_owed\":[\"1005\"]"));
+}
+
+#[test]
+#[should_panic(expected = "ErrSafe64")]
+fn test_collect_fees_quote_overflow() {
+    let mut pool = crate::clmm::pool::test_pool();
+    pool.set_growth_global(10 << 64, 0, Vec::new());
+    let position = ClmmPosition {
+        liquidity: u128::MAX,
+        tick_lower_index: -600,
+        tick_upper_index: 600,
+        ..Default::default()
+    };
+    pool.collect_fees_quote(&[position]);
+}
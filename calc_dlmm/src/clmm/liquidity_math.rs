@@ -0,0 +1,343 @@
+use alloy_primitives::U512;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    clmm::{
+        swap_math::{get_delta_a, get_delta_b},
+        tick_math,
+    },
+    constants,
+    uint_safe::safe64,
+};
+
+// Port of the liquidity <-> amount helpers of src/math/position.ts and of ClmmPoolUtil in
+// src/math/clmm.ts, in exact integer maths where the TS goes through Decimal. Amounts are
+// u64 and liquidities u128 as in the Move contract, larger results abort
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CoinAmounts {
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+// Liquidity to add or remove for one fixed amount, what LiquidityInput holds in the TS
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub liquidity: u128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    // the amounts with the slippage, maxima when rounding up to add liquidity, minima
+    // when rounding down to remove it
+    pub amount_limit_a: u64,
+    pub amount_limit_b: u64,
+    pub fix_amount_a: bool,
+}
+
+// current_sqrt_price: Q64.64 int string
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn coin_amounts_from_liquidity(
+    liquidity: u128,
+    current_sqrt_price: String,
+    lower_tick: i32,
+    upper_tick: i32,
+    round_up: bool,
+) -> CoinAmounts {
+    get_coin_amounts_from_liquidity(
+        liquidity,
+        current_sqrt_price.parse().expect("ErrInvalidSqrtPrice"),
+        tick_math::get_sqrt_price_at_tick(lower_tick),
+        tick_math::get_sqrt_price_at_tick(upper_tick),
+        round_up,
+    )
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn liquidity_from_coin_amounts(
+    current_sqrt_price: String,
+    lower_tick: i32,
+    upper_tick: i32,
+    amount_a: u64,
+    amount_b: u64,
+) -> u128 {
+    estimate_liquidity_from_amounts(
+        current_sqrt_price.parse().expect("ErrInvalidSqrtPrice"),
+        lower_tick,
+        upper_tick,
+        amount_a,
+        amount_b,
+    )
+}
+
+// slippage_bps: basis points applied to the amounts for their limits
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn liquidity_from_one_amount(
+    current_sqrt_price: String,
+    lower_tick: i32,
+    upper_tick: i32,
+    amount: u64,
+    is_coin_a: bool,
+    round_up: bool,
+    slippage_bps: u32,
+) -> LiquidityQuote {
+    estimate_liquidity_from_one_amount(
+        current_sqrt_price.parse().expect("ErrInvalidSqrtPrice"),
+        lower_tick,
+        upper_tick,
+        amount,
+        is_coin_a,
+        round_up,
+        slippage_bps,
+    )
+}
+
+fn checked_u128(value: U512) -> u128 {
+    assert!(value >> 128 == U512::ZERO, "ErrSafe128");
+    value.to::<u128>()
+}
+
+fn div_round(numerator: U512, denominator: U512, round_up: bool) -> U512 {
+    let (quotient, remainder) = numerator.div_rem(denominator);
+    if round_up && remainder != U512::ZERO {
+        quotient + U512::from(1)
+    } else {
+        quotient
+    }
+}
+
+// Token A between two sqrt prices in either order
+pub fn get_coin_a_from_liquidity(
+    liquidity: u128,
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    round_up: bool,
+) -> u64 {
+    safe64(get_delta_a(sqrt_price_0, sqrt_price_1, liquidity, round_up))
+}
+
+pub fn get_coin_b_from_liquidity(
+    liquidity: u128,
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    round_up: bool,
+) -> u64 {
+    safe64(get_delta_b(sqrt_price_0, sqrt_price_1, liquidity, round_up))
+}
+
+// amount * sqrt_p0 * sqrt_p1 / (sqrt_p1 - sqrt_p0)
+pub fn get_liquidity_from_coin_a(
+    amount: u64,
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    round_up: bool,
+) -> u128 {
+    assert!(sqrt_price_0 != sqrt_price_1, "ErrInvalidSqrtPriceRange");
+    let numerator = U512::from(amount) * U512::from(sqrt_price_0) * U512::from(sqrt_price_1);
+    let denominator = U512::from(sqrt_price_0.abs_diff(sqrt_price_1)) << 64;
+    checked_u128(div_round(numerator, denominator, round_up))
+}
+
+// amount / (sqrt_p1 - sqrt_p0)
+pub fn get_liquidity_from_coin_b(
+    amount: u64,
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    round_up: bool,
+) -> u128 {
+    assert!(sqrt_price_0 != sqrt_price_1, "ErrInvalidSqrtPriceRange");
+    let numerator = U512::from(amount) << 64;
+    let denominator = U512::from(sqrt_price_0.abs_diff(sqrt_price_1));
+    checked_u128(div_round(numerator, denominator, round_up))
+}
+
+// Amounts held by a liquidity over [lower_sqrt_price, upper_sqrt_price]: only A below the
+// range, only B above it
+pub fn get_coin_amounts_from_liquidity(
+    liquidity: u128,
+    current_sqrt_price: u128,
+    lower_sqrt_price: u128,
+    upper_sqrt_price: u128,
+    round_up: bool,
+) -> CoinAmounts {
+    assert!(
+        lower_sqrt_price < upper_sqrt_price,
+        "ErrInvalidSqrtPriceRange"
+    );
+    if current_sqrt_price <= lower_sqrt_price {
+        CoinAmounts {
+            amount_a: get_coin_a_from_liquidity(
+                liquidity,
+                lower_sqrt_price,
+                upper_sqrt_price,
+                round_up,
+            ),
+            amount_b: 0,
+        }
+    } else if current_sqrt_price < upper_sqrt_price {
+        CoinAmounts {
+            amount_a: get_coin_a_from_liquidity(
+                liquidity,
+                current_sqrt_price,
+                upper_sqrt_price,
+                round_up,
+            ),
+            amount_b: get_coin_b_from_liquidity(
+                liquidity,
+                lower_sqrt_price,
+                current_sqrt_price,
+                round_up,
+            ),
+        }
+    } else {
+        CoinAmounts {
+            amount_a: 0,
+            amount_b: get_coin_b_from_liquidity(
+                liquidity,
+                lower_sqrt_price,
+                upper_sqrt_price,
+                round_up,
+            ),
+        }
+    }
+}
+
+// Largest liquidity both amounts can provide over [lower_tick, upper_tick], rounded down
+pub fn estimate_liquidity_from_amounts(
+    current_sqrt_price: u128,
+    lower_tick: i32,
+    upper_tick: i32,
+    amount_a: u64,
+    amount_b: u64,
+) -> u128 {
+    assert!(lower_tick < upper_tick, "ErrInvalidTickRange");
+    let lower_sqrt_price = tick_math::get_sqrt_price_at_tick(lower_tick);
+    let upper_sqrt_price = tick_math::get_sqrt_price_at_tick(upper_tick);
+    if current_sqrt_price <= lower_sqrt_price {
+        get_liquidity_from_coin_a(amount_a, lower_sqrt_price, upper_sqrt_price, false)
+    } else if current_sqrt_price < upper_sqrt_price {
+        let liquidity_a =
+            get_liquidity_from_coin_a(amount_a, current_sqrt_price, upper_sqrt_price, false);
+        let liquidity_b =
+            get_liquidity_from_coin_b(amount_b, lower_sqrt_price, current_sqrt_price, false);
+        liquidity_a.min(liquidity_b)
+    } else {
+        get_liquidity_from_coin_b(amount_b, lower_sqrt_price, upper_sqrt_price, false)
+    }
+}
+
+// Liquidity for one fixed amount over [lower_tick, upper_tick] and the amounts it takes.
+// round_up when adding liquidity, down when removing it. Below the range only A can be
+// fixed, above it only B
+pub fn estimate_liquidity_from_one_amount(
+    current_sqrt_price: u128,
+    lower_tick: i32,
+    upper_tick: i32,
+    amount: u64,
+    is_coin_a: bool,
+    round_up: bool,
+    slippage_bps: u32,
+) -> LiquidityQuote {
+    assert!(lower_tick < upper_tick, "ErrInvalidTickRange");
+    let lower_sqrt_price = tick_math::get_sqrt_price_at_tick(lower_tick);
+    let upper_sqrt_price = tick_math::get_sqrt_price_at_tick(upper_tick);
+    let liquidity = if current_sqrt_price <= lower_sqrt_price {
+        assert!(is_coin_a, "ErrCoinNotInRange");
+        get_liquidity_from_coin_a(amount, lower_sqrt_price, upper_sqrt_price, false)
+    } else if current_sqrt_price >= upper_sqrt_price {
+        assert!(!is_coin_a, "ErrCoinNotInRange");
+        get_liquidity_from_coin_b(amount, lower_sqrt_price, upper_sqrt_price, false)
+    } else if is_coin_a {
+        get_liquidity_from_coin_a(amount, current_sqrt_price, upper_sqrt_price, false)
+    } else {
+        get_liquidity_from_coin_b(amount, lower_sqrt_price, current_sqrt_price, false)
+    };
+
+    let amounts = get_coin_amounts_from_liquidity(
+        liquidity,
+        current_sqrt_price,
+        lower_sqrt_price,
+        upper_sqrt_price,
+        round_up,
+    );
+    LiquidityQuote {
+        liquidity,
+        amount_a: amounts.amount_a,
+        amount_b: amounts.amount_b,
+        amount_limit_a: with_slippage(amounts.amount_a, slippage_bps, round_up),
+        amount_limit_b: with_slippage(amounts.amount_b, slippage_bps, round_up),
+        fix_amount_a: is_coin_a,
+    }
+}
+
+// amount * (1 +/- slippage), rounded away from the amount
+fn with_slippage(amount: u64, slippage_bps: u32, up: bool) -> u64 {
+    let basis_point_max = constants::BASIS_POINT_MAX as u128;
+    let limit = if up {
+        (amount as u128 * (basis_point_max + slippage_bps as u128)).div_ceil(basis_point_max)
+    } else {
+        amount as u128 * basis_point_max.saturating_sub(slippage_bps as u128) / basis_point_max
+    };
+    u64::try_from(limit).unwrap_or(u64::MAX)
+}
+
+#[test]
+fn test_liquidity_amounts_round_trip() {
+    let lower = tick_math::get_sqrt_price_at_tick(-1000);
+    let upper = tick_math::get_sqrt_price_at_tick(1000);
+    let liquidity = get_liquidity_from_coin_a(1_000_000_000, lower, upper, false);
+    assert!(get_coin_a_from_liquidity(liquidity, upper, lower, true) <= 1_000_000_000);
+    assert!(get_coin_a_from_liquidity(liquidity + 1, lower, upper, true) >= 1_000_000_000);
+    let liquidity = get_liquidity_from_coin_b(1_000_000_000, lower, upper, true);
+    assert!(get_coin_b_from_liquidity(liquidity, lower, upper, false) >= 1_000_000_000);
+    assert!(get_coin_b_from_liquidity(liquidity - 1, lower, upper, false) < 1_000_000_000);
+
+    // the range sits on both sides of the price 1
+    let amounts = get_coin_amounts_from_liquidity(liquidity, 1 << 64, lower, upper, false);
+    assert!(amounts.amount_a == amounts.amount_b && amounts.amount_a > 0);
+    let below = get_coin_amounts_from_liquidity(liquidity, lower - 1, lower, upper, true);
+    assert!(below.amount_b == 0 && below.amount_a > amounts.amount_a);
+    let above = get_coin_amounts_from_liquidity(liquidity, upper, lower, upper, true);
+    assert!(above.amount_a == 0 && above.amount_b > amounts.amount_b);
+}
+
+#[test]
+fn test_estimate_liquidity() {
+    let quote = estimate_liquidity_from_one_amount(1 << 64, -1000, 1000, 1_000_000, true, true, 50);
+    assert!(quote.fix_amount_a && quote.amount_a <= 1_000_000 && quote.amount_a >= 999_999);
+    // symmetric range around the price 1
+    assert!(quote.amount_b.abs_diff(quote.amount_a) <= 1);
+    assert!(quote.amount_limit_a == quote.amount_a + quote.amount_a.div_ceil(200));
+    let liquidity = liquidity_from_coin_amounts(
+        (1u128 << 64).to_string(),
+        -1000,
+        1000,
+        quote.amount_a,
+        quote.amount_b,
+    );
+    assert!(liquidity.abs_diff(quote.liquidity) <= 1);
+    // the smaller amount limits the liquidity
+    let liquidity_b = estimate_liquidity_from_amounts(1 << 64, -1000, 1000, 1_000_000, 1_000);
+    assert!(liquidity_b < quote.liquidity / 100);
+
+    let decrease = liquidity_from_one_amount(
+        tick_math::tick_index_to_sqrt_price_x64(2000),
+        -1000,
+        1000,
+        1_000_000,
+        false,
+        false,
+        100,
+    );
+    assert!(decrease.amount_a == 0 && decrease.amount_b <= 1_000_000);
+    assert!(decrease.amount_limit_b == decrease.amount_b * 99 / 100);
+}
+
+#[test]
+#[should_panic(expected = "ErrCoinNotInRange")]
+fn test_estimate_liquidity_wrong_coin() {
+    let lower = tick_math::get_sqrt_price_at_tick(-2000);
+    estimate_liquidity_from_one_amount(lower, -1000, 1000, 1_000_000, false, true, 0);
+}
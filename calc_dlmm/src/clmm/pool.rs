@@ -0,0 +1,611 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    clmm::{
+        swap_math::{self, FEE_RATE_DENOMINATOR},
+        tick_math::{self, MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64},
+    },
+    units::{int_string, int_string_vec, u64_string},
+};
+
+// Snapshot of a CLMM pool and its initialized ticks, quoted like computeSwap of
+// src/math/clmm.ts. Ticks are kept ordered so that a swap walks them without a tick list
+// prepared by the caller
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClmmPool {
+    #[serde(with = "int_string")]
+    pub(crate) current_sqrt_price: u128,
+    pub(crate) current_tick_index: i32,
+    #[serde(with = "int_string")]
+    pub(crate) liquidity: u128,
+    // parts of 1e6
+    #[serde(with = "u64_string")]
+    pub(crate) fee_rate: u64,
+    // share of the fee, parts of 1e6
+    #[serde(with = "u64_string")]
+    pub(crate) protocol_fee_rate: u64,
+    // a list ordered by index when serialized
+    #[serde(with = "ticks_by_index")]
+    pub(crate) ticks: BTreeMap<i32, ClmmTick>,
+    #[serde(default)]
+    pub(crate) coin_type_a: String,
+    #[serde(default)]
+    pub(crate) coin_type_b: String,
+    // fee and rewarder growths per unit of liquidity, Q64.64 wrapping around as in Move
+    #[serde(default, with = "int_string")]
+    pub(crate) fee_growth_global_a: u128,
+    #[serde(default, with = "int_string")]
+    pub(crate) fee_growth_global_b: u128,
+    #[serde(default, with = "int_string_vec")]
+    pub(crate) rewarder_growth_global: Vec<u128>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClmmTick {
+    pub index: i32,
+    // liquidity added when the price crosses the tick upwards
+    #[serde(with = "int_string")]
+    pub liquidity_net: i128,
+    // growths on the other side of the tick from the current price, flipped on crossing
+    #[serde(default, with = "int_string")]
+    pub fee_growth_outside_a: u128,
+    #[serde(default, with = "int_string")]
+    pub fee_growth_outside_b: u128,
+    #[serde(default, with = "int_string_vec")]
+    pub rewarders_growth_outside: Vec<u128>,
+}
+
+// One range of constant liquidity crossed by a swap
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClmmSwapStep {
+    pub liquidity: u128,
+    start_sqrt_price: u128,
+    next_sqrt_price: u128,
+    // without the fee
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    // parts of the fee, the rest goes to the liquidity providers
+    pub protocol_fee: u64,
+    pub ref_fee: u64,
+    // tick crossed at the end of the step
+    pub crossed_tick: Option<i32>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClmmSwapResult {
+    pub a2b: bool,
+    // fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub ref_fee: u64,
+    // amount in, or amount out when quoting by amount out, the pool couldn't fill before the
+    // sqrt price limit
+    pub amount_left: u64,
+    next_sqrt_price: u128,
+    pub next_tick_index: i32,
+    pub next_liquidity: u128,
+    steps: Vec<ClmmSwapStep>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ClmmSwapStep {
+    // Q64.64 int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn start_sqrt_price_x64(&self) -> String {
+        self.start_sqrt_price.to_string()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn next_sqrt_price_x64(&self) -> String {
+        self.next_sqrt_price.to_string()
+    }
+}
+
+impl ClmmSwapStep {
+    pub fn start_sqrt_price(&self) -> u128 {
+        self.start_sqrt_price
+    }
+
+    pub fn next_sqrt_price(&self) -> u128 {
+        self.next_sqrt_price
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ClmmSwapResult {
+    // Q64.64 int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn next_sqrt_price_x64(&self) -> String {
+        self.next_sqrt_price.to_string()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn steps(&self) -> Vec<ClmmSwapStep> {
+        self.steps.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn crossed_ticks(&self) -> u32 {
+        self.steps
+            .iter()
+            .filter(|step| step.crossed_tick.is_some())
+            .count() as u32
+    }
+}
+
+impl ClmmSwapResult {
+    pub fn next_sqrt_price(&self) -> u128 {
+        self.next_sqrt_price
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ClmmPool {
+    // sqrt_price_limit: Q64.64 int string, the min or max sqrt price when omitted.
+    // ref_fee_rate: parts of 1e6 of the fee going to the referrer.
+    // Native users call swap with the limit as a number
+    #[cfg(feature = "wasm")]
+    pub fn get_swap_out(
+        &self,
+        amount_in: u64,
+        a2b: bool,
+        sqrt_price_limit: Option<String>,
+        ref_fee_rate: u64,
+    ) -> Result<ClmmSwapResult, JsError> {
+        let sqrt_price_limit = parse_sqrt_price_limit(sqrt_price_limit)?;
+        Ok(self.swap(a2b, true, amount_in, sqrt_price_limit, ref_fee_rate))
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn get_swap_in(
+        &self,
+        amount_out: u64,
+        a2b: bool,
+        sqrt_price_limit: Option<String>,
+        ref_fee_rate: u64,
+    ) -> Result<ClmmSwapResult, JsError> {
+        let sqrt_price_limit = parse_sqrt_price_limit(sqrt_price_limit)?;
+        Ok(self.swap(a2b, false, amount_out, sqrt_price_limit, ref_fee_rate))
+    }
+
+    pub fn set_coin_types(&mut self, coin_type_a: String, coin_type_b: String) {
+        self.coin_type_a = coin_type_a;
+        self.coin_type_b = coin_type_b;
+    }
+
+    // Q64.64 int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn current_sqrt_price_x64(&self) -> String {
+        self.current_sqrt_price.to_string()
+    }
+
+    // Pool snapshot: { current_sqrt_price, current_tick_index, liquidity, fee_rate,
+    // protocol_fee_rate, ticks: { index, liquidity_net, fee_growth_outside_a?,
+    // fee_growth_outside_b?, rewarders_growth_outside? }[], fee_growth_global_a?,
+    // fee_growth_global_b?, rewarder_growth_global?, coin_type_a?, coin_type_b? }
+    #[cfg(feature = "wasm")]
+    pub fn from_json(json: &str) -> Result<ClmmPool, JsError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(feature = "wasm")]
+fn parse_sqrt_price_limit(sqrt_price_limit: Option<String>) -> Result<Option<u128>, JsError> {
+    sqrt_price_limit
+        .map(|limit| {
+            limit
+                .parse()
+                .map_err(|_| JsError::new("ErrInvalidSqrtPrice"))
+        })
+        .transpose()
+}
+
+impl ClmmPool {
+    pub fn new(
+        current_sqrt_price: u128,
+        liquidity: u128,
+        fee_rate: u64,
+        protocol_fee_rate: u64,
+        ticks: Vec<ClmmTick>,
+    ) -> Self {
+        assert!(fee_rate < FEE_RATE_DENOMINATOR, "ErrInvalidFeeRate");
+        assert!(
+            protocol_fee_rate <= FEE_RATE_DENOMINATOR,
+            "ErrInvalidFeeRate"
+        );
+        ClmmPool {
+            current_sqrt_price,
+            current_tick_index: tick_math::get_tick_at_sqrt_price(current_sqrt_price),
+            liquidity,
+            fee_rate,
+            protocol_fee_rate,
+            ticks: ticks.into_iter().map(|tick| (tick.index, tick)).collect(),
+            coin_type_a: String::new(),
+            coin_type_b: String::new(),
+            fee_growth_global_a: 0,
+            fee_growth_global_b: 0,
+            rewarder_growth_global: Vec::new(),
+        }
+    }
+
+    // Up to 3 rewarders
+    pub fn set_growth_global(
+        &mut self,
+        fee_growth_global_a: u128,
+        fee_growth_global_b: u128,
+        rewarder_growth_global: Vec<u128>,
+    ) {
+        assert!(rewarder_growth_global.len() <= 3, "ErrTooManyRewarders");
+        self.fee_growth_global_a = fee_growth_global_a;
+        self.fee_growth_global_b = fee_growth_global_b;
+        self.rewarder_growth_global = rewarder_growth_global;
+    }
+
+    pub fn coin_types(&self) -> (&str, &str) {
+        (&self.coin_type_a, &self.coin_type_b)
+    }
+
+    pub fn current_sqrt_price(&self) -> u128 {
+        self.current_sqrt_price
+    }
+
+    pub fn current_tick_index(&self) -> i32 {
+        self.current_tick_index
+    }
+
+    pub fn liquidity(&self) -> u128 {
+        self.liquidity
+    }
+
+    pub fn fee_rate(&self) -> u64 {
+        self.fee_rate
+    }
+
+    pub fn protocol_fee_rate(&self) -> u64 {
+        self.protocol_fee_rate
+    }
+
+    // Ordered by index
+    pub fn ticks(&self) -> impl Iterator<Item = &ClmmTick> {
+        self.ticks.values()
+    }
+
+    // Add or replace a tick
+    pub fn insert_tick(&mut self, tick: ClmmTick) {
+        self.ticks.insert(tick.index, tick);
+    }
+
+    // Selling A walks down to the ticks at or below the current one, selling B walks up
+    fn next_tick(&self, a2b: bool, current_tick_index: i32) -> Option<&ClmmTick> {
+        if a2b {
+            self.ticks
+                .range(..=current_tick_index)
+                .next_back()
+                .map(|(_, tick)| tick)
+        } else {
+            self.ticks
+                .range(current_tick_index + 1..)
+                .next()
+                .map(|(_, tick)| tick)
+        }
+    }
+
+    // Protocol fee rounded up, ref fee rounded down, as ClmmPoolUtil.updateFeeRate
+    fn split_fee(&self, fee: u64, ref_fee_rate: u64) -> (u64, u64) {
+        let protocol_fee = (fee as u128 * self.protocol_fee_rate as u128)
+            .div_ceil(FEE_RATE_DENOMINATOR as u128) as u64;
+        let ref_fee = (fee as u128 * ref_fee_rate as u128 / FEE_RATE_DENOMINATOR as u128) as u64;
+        (protocol_fee, ref_fee)
+    }
+
+    // amount: amount in fee included when by_amount_in, amount out otherwise
+    pub fn swap(
+        &self,
+        a2b: bool,
+        by_amount_in: bool,
+        amount: u64,
+        sqrt_price_limit: Option<u128>,
+        ref_fee_rate: u64,
+    ) -> ClmmSwapResult {
+        assert!(ref_fee_rate <= FEE_RATE_DENOMINATOR, "ErrInvalidFeeRate");
+        let sqrt_price_limit = sqrt_price_limit.unwrap_or(if a2b {
+            MIN_SQRT_PRICE_X64
+        } else {
+            MAX_SQRT_PRICE_X64
+        });
+        if a2b {
+            assert!(
+                sqrt_price_limit >= MIN_SQRT_PRICE_X64
+                    && sqrt_price_limit <= self.current_sqrt_price,
+                "ErrInvalidSqrtPriceLimit"
+            );
+        } else {
+            assert!(
+                sqrt_price_limit <= MAX_SQRT_PRICE_X64
+                    && sqrt_price_limit >= self.current_sqrt_price,
+                "ErrInvalidSqrtPriceLimit"
+            );
+        }
+
+        let mut result = ClmmSwapResult {
+            a2b,
+            ..Default::default()
+        };
+        let mut amount_left = amount;
+        let mut sqrt_price = self.current_sqrt_price;
+        let mut tick_index = self.current_tick_index;
+        let mut liquidity = self.liquidity;
+        while amount_left > 0 && sqrt_price != sqrt_price_limit {
+            let next_tick = self.next_tick(a2b, tick_index);
+            let target_sqrt_price = match next_tick {
+                Some(tick) => {
+                    let tick_sqrt_price = tick_math::get_sqrt_price_at_tick(tick.index);
+                    if a2b {
+                        tick_sqrt_price.max(sqrt_price_limit)
+                    } else {
+                        tick_sqrt_price.min(sqrt_price_limit)
+                    }
+                }
+                None => sqrt_price_limit,
+            };
+
+            let step = swap_math::compute_swap_step(
+                sqrt_price,
+                target_sqrt_price,
+                liquidity,
+                amount_left,
+                self.fee_rate,
+                by_amount_in,
+            );
+            amount_left -= if by_amount_in {
+                step.amount_in + step.fee
+            } else {
+                step.amount_out
+            };
+            let (protocol_fee, ref_fee) = self.split_fee(step.fee, ref_fee_rate);
+            result.amount_in += step.amount_in + step.fee;
+            result.amount_out += step.amount_out;
+            result.fee += step.fee;
+            result.protocol_fee += protocol_fee;
+            result.ref_fee += ref_fee;
+
+            let crossed_tick = next_tick.filter(|tick| {
+                step.next_sqrt_price == tick_math::get_sqrt_price_at_tick(tick.index)
+            });
+            result.steps.push(ClmmSwapStep {
+                liquidity,
+                start_sqrt_price: sqrt_price,
+                next_sqrt_price: step.next_sqrt_price,
+                amount_in: step.amount_in,
+                amount_out: step.amount_out,
+                fee: step.fee,
+                protocol_fee,
+                ref_fee,
+                crossed_tick: crossed_tick.map(|tick| tick.index),
+            });
+
+            sqrt_price = step.next_sqrt_price;
+            match crossed_tick {
+                Some(tick) if a2b => {
+                    liquidity = liquidity
+                        .checked_add_signed(-tick.liquidity_net)
+                        .expect("ErrInvalidLiquidityNet");
+                    tick_index = tick.index - 1;
+                }
+                Some(tick) => {
+                    liquidity = liquidity
+                        .checked_add_signed(tick.liquidity_net)
+                        .expect("ErrInvalidLiquidityNet");
+                    tick_index = tick.index;
+                }
+                None => tick_index = tick_math::get_tick_at_sqrt_price(sqrt_price),
+            };
+        }
+
+        result.amount_left = amount_left;
+        result.next_sqrt_price = sqrt_price;
+        result.next_tick_index = tick_index;
+        result.next_liquidity = liquidity;
+        result
+    }
+
+    // Move the pool to the end of a swap quoted on its current state: the price, the
+    // liquidity, the fee growths and the growths outside of the crossed ticks
+    pub fn apply_swap(&mut self, result: &ClmmSwapResult) {
+        if let Some(first) = result.steps.first() {
+            assert!(
+                first.start_sqrt_price == self.current_sqrt_price,
+                "ErrStaleSwap"
+            );
+        }
+        for step in &result.steps {
+            let lp_fee = step
+                .fee
+                .saturating_sub(step.protocol_fee)
+                .saturating_sub(step.ref_fee);
+            if lp_fee > 0 && step.liquidity > 0 {
+                let growth = ((lp_fee as u128) << 64) / step.liquidity;
+                if result.a2b {
+                    self.fee_growth_global_a = self.fee_growth_global_a.wrapping_add(growth);
+                } else {
+                    self.fee_growth_global_b = self.fee_growth_global_b.wrapping_add(growth);
+                }
+            }
+
+            if let Some(index) = step.crossed_tick {
+                let tick = self.ticks.get_mut(&index).expect("ErrTickNotFound");
+                tick.fee_growth_outside_a = self
+                    .fee_growth_global_a
+                    .wrapping_sub(tick.fee_growth_outside_a);
+                tick.fee_growth_outside_b = self
+                    .fee_growth_global_b
+                    .wrapping_sub(tick.fee_growth_outside_b);
+                let rewarders = self.rewarder_growth_global.len();
+                if tick.rewarders_growth_outside.len() < rewarders {
+                    tick.rewarders_growth_outside.resize(rewarders, 0);
+                }
+                for (outside, global) in tick
+                    .rewarders_growth_outside
+                    .iter_mut()
+                    .zip(&self.rewarder_growth_global)
+                {
+                    *outside = global.wrapping_sub(*outside);
+                }
+            }
+        }
+        self.current_sqrt_price = result.next_sqrt_price;
+        self.current_tick_index = result.next_tick_index;
+        self.liquidity = result.next_liquidity;
+    }
+}
+
+mod ticks_by_index {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    use super::ClmmTick;
+
+    pub fn serialize<S: Serializer>(
+        ticks: &BTreeMap<i32, ClmmTick>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ticks.values())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<i32, ClmmTick>, D::Error> {
+        let ticks = Vec::<ClmmTick>::deserialize(deserializer)?;
+        Ok(ticks.into_iter().map(|tick| (tick.index, tick)).collect())
+    }
+}
+
+// One position of liquidity 1e12 over [-600, 600) and another of 1e12 over [0, 1200)
+#[cfg(test)]
+pub(crate) fn test_pool() -> ClmmPool {
+    let ticks = vec![
+        ClmmTick {
+            index: -600,
+            liquidity_net: 1_000_000_000_000,
+            ..Default::default()
+        },
+        ClmmTick {
+            index: 0,
+            liquidity_net: 1_000_000_000_000,
+            ..Default::default()
+        },
+        ClmmTick {
+            index: 600,
+            liquidity_net: -1_000_000_000_000,
+            ..Default::default()
+        },
+        ClmmTick {
+            index: 1200,
+            liquidity_net: -1_000_000_000_000,
+            ..Default::default()
+        },
+    ];
+    // 0.25% fee, 20% of it to the protocol
+    ClmmPool::new(1 << 64, 2_000_000_000_000, 2500, 200_000, ticks)
+}
+
+#[test]
+fn test_swap_within_range() {
+    let pool = test_pool();
+    let result = pool.swap(true, true, 1_000_000, None, 0);
+    assert!(result.amount_in == 1_000_000 && result.amount_left == 0);
+    assert!(result.fee == 2500 && result.protocol_fee == 500);
+    assert!(result.amount_out < 997_500 && result.amount_out > 997_000);
+    // the price sits on the initialized tick 0, selling A first crosses it for free and
+    // leaves the [0, 1200) position out of range
+    let steps = result.steps();
+    assert!(steps.len() == 2 && result.crossed_ticks() == 1);
+    assert!(steps[0].crossed_tick == Some(0) && steps[0].amount_in == 0);
+    assert!(steps[1].liquidity == 1_000_000_000_000);
+    assert!(result.next_tick_index == -1);
+    assert!(result.next_sqrt_price() < pool.current_sqrt_price());
+
+    // the same swap quoted by amount out needs the same amount in
+    let result_in = pool.swap(true, false, result.amount_out, None, 0);
+    assert!(result_in.amount_out == result.amount_out && result_in.amount_left == 0);
+    assert!(result_in.amount_in <= result.amount_in);
+    assert!(result_in.amount_in + 2 >= result.amount_in);
+}
+
+#[test]
+fn test_swap_crossing_ticks() {
+    let pool = test_pool();
+    // B in up through the tick 600 where half of the liquidity ends
+    let result = pool.swap(false, true, 100_000_000_000, None, 100_000);
+    let steps = result.steps();
+    assert!(steps[0].crossed_tick == Some(600) && steps[0].liquidity == 2_000_000_000_000);
+    assert!(steps[1].crossed_tick == Some(1200) && steps[1].liquidity == 1_000_000_000_000);
+    // no liquidity above 1200, the rest walks up to the max price without filling
+    assert!(steps.len() == 3 && steps[2].liquidity == 0 && steps[2].amount_in == 0);
+    assert!(result.amount_left > 0 && result.next_sqrt_price() == MAX_SQRT_PRICE_X64);
+    assert!(result.amount_in + result.amount_left == 100_000_000_000);
+    // 10% of the fee to the referrer, rounded down per step
+    let fees: u64 = steps.iter().map(|step| step.fee).sum();
+    assert!(fees == result.fee && result.ref_fee + 2 >= result.fee / 10);
+
+    // the sqrt price limit stops the swap at the first tick
+    let limit = tick_math::get_sqrt_price_at_tick(600);
+    let limited = pool.swap(false, true, 100_000_000_000, Some(limit), 0);
+    assert!(limited.next_sqrt_price() == limit && limited.next_tick_index == 600);
+    assert!(limited.amount_in == steps[0].amount_in + steps[0].fee);
+    assert!(limited.amount_out == steps[0].amount_out);
+}
+
+#[test]
+fn test_apply_swap() {
+    let mut pool = test_pool();
+    let result = pool.swap(false, true, 70_000_000_000, None, 0);
+    assert!(result.steps()[0].crossed_tick == Some(600));
+    pool.apply_swap(&result);
+    assert!(pool.current_sqrt_price() == result.next_sqrt_price());
+    assert!(pool.current_tick_index() == result.next_tick_index);
+    assert!(pool.current_tick_index() > 600 && pool.liquidity() == 1_000_000_000_000);
+    // the LP fees of the B in grow the global B growth, and tick 600 saw all of it below
+    assert!(pool.fee_growth_global_a == 0 && pool.fee_growth_global_b > 0);
+    let tick_600 = &pool.ticks[&600];
+    let steps = result.steps();
+    let lp_fee = steps[0].fee - steps[0].protocol_fee;
+    assert!(tick_600.fee_growth_outside_b == ((lp_fee as u128) << 64) / steps[0].liquidity);
+    // quoting again from the new state continues where the swap ended
+    let next = pool.swap(false, true, 1_000_000, None, 0);
+    assert!(next.steps()[0].start_sqrt_price() == pool.current_sqrt_price());
+}
+
+#[test]
+#[should_panic(expected = "ErrStaleSwap")]
+fn test_apply_swap_twice() {
+    let mut pool = test_pool();
+    let result = pool.swap(true, true, 1_000_000, None, 0);
+    pool.apply_swap(&result);
+    pool.apply_swap(&result);
+}
+
+#[test]
+fn test_pool_json() {
+    let pool = test_pool();
+    let json = serde_json::to_string(&pool).unwrap();
+    assert!(json.contains("\"liquidity_net\":\"-1000000000000\""));
+    let decoded: ClmmPool = serde_json::from_str(&json).unwrap();
+    assert!(decoded.ticks().eq(pool.ticks()));
+    assert!(decoded.current_sqrt_price_x64() == "18446744073709551616");
+}
@@ -0,0 +1,301 @@
+use alloy_primitives::{U256, U512, ruint::UintTryFrom};
+
+use crate::{
+    clmm::tick_math::{MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64},
+    uint_safe::safe64,
+};
+
+// Port of the swap step maths of src/math/clmm.ts and of the clmm_math Move module. Amounts
+// are in raw token units, liquidity is sqrt(x * y) and sqrt prices are Q64.64 of B per A
+
+// Fee rates are parts of 1e6
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapStepResult {
+    // without the fee
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub next_sqrt_price: u128,
+    pub fee: u64,
+}
+
+fn sqrt_price_diff(sqrt_price_0: u128, sqrt_price_1: u128) -> U512 {
+    U512::from(sqrt_price_0.abs_diff(sqrt_price_1))
+}
+
+fn div_round_up(numerator: U512, denominator: U512) -> U512 {
+    let (quotient, remainder) = numerator.div_rem(denominator);
+    if remainder == U512::ZERO {
+        quotient
+    } else {
+        quotient + U512::from(1)
+    }
+}
+
+// Token A: liquidity * (1 / sqrt_p0 - 1 / sqrt_p1), token B: liquidity * (sqrt_p1 - sqrt_p0)
+pub fn get_delta_a(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> U256 {
+    let diff = sqrt_price_diff(sqrt_price_0, sqrt_price_1);
+    if liquidity == 0 || diff == U512::ZERO {
+        return U256::ZERO;
+    }
+    let numerator = (U512::from(liquidity) * diff) << 64;
+    let denominator = U512::from(sqrt_price_0) * U512::from(sqrt_price_1);
+    let delta = if round_up {
+        div_round_up(numerator, denominator)
+    } else {
+        numerator / denominator
+    };
+    // the denominator is at least the diff, delta <= liquidity << 64
+    U256::uint_try_from(delta).expect("ErrDeltaOverflow")
+}
+
+pub fn get_delta_b(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> U256 {
+    let diff = sqrt_price_diff(sqrt_price_0, sqrt_price_1);
+    if liquidity == 0 || diff == U512::ZERO {
+        return U256::ZERO;
+    }
+    let product = U512::from(liquidity) * diff;
+    let delta = if round_up {
+        div_round_up(product, U512::from(1) << 64)
+    } else {
+        product >> 64
+    };
+    // delta < liquidity << 64
+    U256::uint_try_from(delta).expect("ErrDeltaOverflow")
+}
+
+// Amount in of the token sold to move the price to target_sqrt_price, rounded up
+pub fn get_delta_up_from_input(
+    current_sqrt_price: u128,
+    target_sqrt_price: u128,
+    liquidity: u128,
+    a2b: bool,
+) -> U256 {
+    if a2b {
+        get_delta_a(current_sqrt_price, target_sqrt_price, liquidity, true)
+    } else {
+        get_delta_b(current_sqrt_price, target_sqrt_price, liquidity, true)
+    }
+}
+
+// Amount out of the token bought when the price moves to target_sqrt_price, rounded down
+pub fn get_delta_down_from_output(
+    current_sqrt_price: u128,
+    target_sqrt_price: u128,
+    liquidity: u128,
+    a2b: bool,
+) -> U256 {
+    if a2b {
+        get_delta_b(current_sqrt_price, target_sqrt_price, liquidity, false)
+    } else {
+        get_delta_a(current_sqrt_price, target_sqrt_price, liquidity, false)
+    }
+}
+
+// sqrt_price * liquidity / (liquidity +/- amount * sqrt_price), rounded up
+pub fn get_next_sqrt_price_a_up(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    by_amount_in: bool,
+) -> u128 {
+    if amount == 0 {
+        return sqrt_price;
+    }
+    let numerator = (U512::from(sqrt_price) * U512::from(liquidity)) << 64;
+    let liquidity_shl_64 = U512::from(liquidity) << 64;
+    let product = U512::from(sqrt_price) * U512::from(amount);
+    let next_sqrt_price = if by_amount_in {
+        div_round_up(numerator, liquidity_shl_64 + product)
+    } else {
+        assert!(liquidity_shl_64 > product, "ErrNotEnoughLiquidity");
+        div_round_up(numerator, liquidity_shl_64 - product)
+    };
+    check_sqrt_price(next_sqrt_price)
+}
+
+// sqrt_price +/- amount / liquidity, rounded down
+pub fn get_next_sqrt_price_b_down(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    by_amount_in: bool,
+) -> u128 {
+    assert!(liquidity > 0, "ErrNotEnoughLiquidity");
+    let amount_shl_64 = U512::from(amount) << 64;
+    let delta = if by_amount_in {
+        amount_shl_64 / U512::from(liquidity)
+    } else {
+        div_round_up(amount_shl_64, U512::from(liquidity))
+    };
+    let next_sqrt_price = if by_amount_in {
+        U512::from(sqrt_price) + delta
+    } else {
+        assert!(delta <= U512::from(sqrt_price), "ErrSqrtPriceOutOfBounds");
+        U512::from(sqrt_price) - delta
+    };
+    check_sqrt_price(next_sqrt_price)
+}
+
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    a2b: bool,
+) -> u128 {
+    if a2b {
+        get_next_sqrt_price_a_up(sqrt_price, liquidity, amount, true)
+    } else {
+        get_next_sqrt_price_b_down(sqrt_price, liquidity, amount, true)
+    }
+}
+
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    a2b: bool,
+) -> u128 {
+    if a2b {
+        get_next_sqrt_price_b_down(sqrt_price, liquidity, amount, false)
+    } else {
+        get_next_sqrt_price_a_up(sqrt_price, liquidity, amount, false)
+    }
+}
+
+fn check_sqrt_price(sqrt_price: U512) -> u128 {
+    assert!(
+        sqrt_price >= U512::from(MIN_SQRT_PRICE_X64)
+            && sqrt_price <= U512::from(MAX_SQRT_PRICE_X64),
+        "ErrSqrtPriceOutOfBounds"
+    );
+    sqrt_price.to::<u128>()
+}
+
+// Fee on top of an amount in net of fees, rounded up
+fn fee_on_amount_in(amount_in: u64, fee_rate: u64) -> u64 {
+    let numerator = amount_in as u128 * fee_rate as u128;
+    let denominator = (FEE_RATE_DENOMINATOR - fee_rate) as u128;
+    numerator.div_ceil(denominator) as u64
+}
+
+// Swap within a range of constant liquidity, from current_sqrt_price towards
+// target_sqrt_price. `amount` is the amount in fee included when by_amount_in, the amount
+// out otherwise
+pub fn compute_swap_step(
+    current_sqrt_price: u128,
+    target_sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    fee_rate: u64,
+    by_amount_in: bool,
+) -> SwapStepResult {
+    assert!(fee_rate < FEE_RATE_DENOMINATOR, "ErrInvalidFeeRate");
+    if liquidity == 0 {
+        return SwapStepResult {
+            next_sqrt_price: target_sqrt_price,
+            ..Default::default()
+        };
+    }
+    let a2b = current_sqrt_price >= target_sqrt_price;
+    if by_amount_in {
+        let amount_remain = (amount as u128 * (FEE_RATE_DENOMINATOR - fee_rate) as u128
+            / FEE_RATE_DENOMINATOR as u128) as u64;
+        let max_amount_in =
+            get_delta_up_from_input(current_sqrt_price, target_sqrt_price, liquidity, a2b);
+        let (amount_in, fee, next_sqrt_price) = if max_amount_in > U256::from(amount_remain) {
+            let next_sqrt_price =
+                get_next_sqrt_price_from_input(current_sqrt_price, liquidity, amount_remain, a2b);
+            (amount_remain, amount - amount_remain, next_sqrt_price)
+        } else {
+            let amount_in = safe64(max_amount_in);
+            (
+                amount_in,
+                fee_on_amount_in(amount_in, fee_rate),
+                target_sqrt_price,
+            )
+        };
+        let amount_out =
+            get_delta_down_from_output(current_sqrt_price, next_sqrt_price, liquidity, a2b);
+        SwapStepResult {
+            amount_in,
+            amount_out: safe64(amount_out),
+            next_sqrt_price,
+            fee,
+        }
+    } else {
+        let max_amount_out =
+            get_delta_down_from_output(current_sqrt_price, target_sqrt_price, liquidity, a2b);
+        let (amount_out, next_sqrt_price) = if max_amount_out > U256::from(amount) {
+            let next_sqrt_price =
+                get_next_sqrt_price_from_output(current_sqrt_price, liquidity, amount, a2b);
+            (amount, next_sqrt_price)
+        } else {
+            (safe64(max_amount_out), target_sqrt_price)
+        };
+        let amount_in = safe64(get_delta_up_from_input(
+            current_sqrt_price,
+            next_sqrt_price,
+            liquidity,
+            a2b,
+        ));
+        SwapStepResult {
+            amount_in,
+            amount_out,
+            next_sqrt_price,
+            fee: fee_on_amount_in(amount_in, fee_rate),
+        }
+    }
+}
+
+#[test]
+fn test_deltas() {
+    let one = 1u128 << 64;
+    let two = 2u128 << 64;
+    // from price 1 to 4 with liquidity 1000: 500 A, 1000 B
+    assert!(get_delta_a(one, two, 1000, false) == U256::from(500));
+    assert!(get_delta_b(one, two, 1000, false) == U256::from(1000));
+    assert!(get_delta_a(one, one + 1, 1000, true) == U256::from(1));
+    assert!(get_delta_a(one, one + 1, 1000, false) == U256::ZERO);
+    assert!(get_delta_b(two, one, 0, true) == U256::ZERO);
+
+    // selling A lowers the price, selling B raises it
+    assert!(get_next_sqrt_price_from_input(two, 1000, 500, true) == one);
+    assert!(get_next_sqrt_price_from_input(one, 1000, 1000, false) == two);
+    assert!(get_next_sqrt_price_from_output(two, 1000, 1000, true) == one);
+    assert!(get_next_sqrt_price_from_output(one, 1000, 500, false) == two);
+}
+
+#[test]
+fn test_compute_swap_step() {
+    let one = 1u128 << 64;
+    let two = 2u128 << 64;
+    let liquidity = 1_000_000_000u128;
+    // 0.3% fee, the whole 1000 A in stays within the range
+    let step = compute_swap_step(two, one, liquidity, 1000, 3000, true);
+    assert!(step.amount_in == 997 && step.fee == 3);
+    assert!(step.next_sqrt_price < two && step.next_sqrt_price > one);
+    assert!(step.amount_out == 3987);
+
+    // the range only takes 5e8 A
+    let step = compute_swap_step(two, one, liquidity, 10_000_000_000, 3000, true);
+    assert!(step.next_sqrt_price == one);
+    assert!(step.amount_in == 500_000_000 && step.fee == 1_504_514);
+    assert!(step.amount_out == 1_000_000_000);
+
+    // exact out mirrors exact in
+    let step_out = compute_swap_step(two, one, liquidity, 3987, 3000, false);
+    assert!(step_out.amount_out == 3987 && step_out.amount_in <= 997);
+    assert!(compute_swap_step(two, one, 0, 1000, 3000, true).next_sqrt_price == one);
+}
@@ -0,0 +1,313 @@
+use alloy_primitives::U256;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{price::Rounding, units::PriceX128};
+
+// Port of TickMath (src/math/tick.ts) and of the tick_math Move module: the sqrt price of
+// tick i is sqrt(1.0001^i) as a Q64.64 number, of token B per token A in raw units
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
+
+// log_sqrt(1.0001)(2) as a 32.32 number, and the error margins of the 14 bit log2
+const LOG_B_2_X32: i128 = 59543866431248;
+const LOG_B_P_ERR_MARGIN_LOWER_X64: i128 = 184467440737095516;
+const LOG_B_P_ERR_MARGIN_UPPER_X64: i128 = 15793534762490258745;
+const BIT_PRECISION: u32 = 14;
+
+// sqrt(1.0001)^(2^i) as Q32.96 for i in 1..=18, bit 0 is the starting ratio
+const POSITIVE_RATIOS_X96: [u128; 18] = [
+    79236085330515764027303304731,
+    79244008939048815603706035061,
+    79259858533276714757314932305,
+    79291567232598584799939703904,
+    79355022692464371645785046466,
+    79482085999252804386437311141,
+    79736823300114093921829183326,
+    80248749790819932309965073892,
+    81282483887344747381513967011,
+    83390072131320151908154831281,
+    87770609709833776024991924138,
+    97234110755111693312479820773,
+    119332217159966728226237229890,
+    179736315981702064433883588727,
+    407748233172238350107850275304,
+    2098478828474011932436660412517,
+    55581415166113811149459800483533,
+    38992368544603139932233054999993551,
+];
+
+// sqrt(1.0001)^-(2^i) as Q64.64 for i in 1..=18
+const NEGATIVE_RATIOS_X64: [u128; 18] = [
+    18444899583751176498,
+    18443055278223354162,
+    18439367220385604838,
+    18431993317065449817,
+    18417254355718160513,
+    18387811781193591352,
+    18329067761203520168,
+    18212142134806087854,
+    17980523815641551639,
+    17526086738831147013,
+    16651378430235024244,
+    15030750278693429944,
+    12247334978882834399,
+    8131365268884726200,
+    3584323654723342297,
+    696457651847595233,
+    26294789957452057,
+    37481735321082,
+];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+// Return the Q64.64 sqrt price as an int string
+pub fn tick_index_to_sqrt_price_x64(tick: i32) -> String {
+    get_sqrt_price_at_tick(tick).to_string()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+// sqrt_price: Q64.64 int string. Return the largest tick whose sqrt price is <= sqrt_price
+pub fn sqrt_price_x64_to_tick_index(sqrt_price: String) -> i32 {
+    get_tick_at_sqrt_price(sqrt_price.parse().expect("ErrInvalidSqrtPrice"))
+}
+
+// price: decimal string of token B per token A in UI units, e.g. "1.2345" or "3e-7".
+// Return the Q64.64 sqrt price as an int string, rounded down
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn price_to_sqrt_price_x64(price: String, decimals_a: u8, decimals_b: u8) -> String {
+    get_sqrt_price_from_price(&price, decimals_a, decimals_b).to_string()
+}
+
+// Return the price as a decimal string of token B per token A in UI units, with exactly
+// `precision` fractional digits
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn sqrt_price_x64_to_price(
+    sqrt_price: String,
+    decimals_a: u8,
+    decimals_b: u8,
+    precision: u8,
+    rounding: Rounding,
+) -> String {
+    let sqrt_price: u128 = sqrt_price.parse().expect("ErrInvalidSqrtPrice");
+    sqrt_price_to_price_x128(sqrt_price).to_ui_decimal(decimals_a, decimals_b, precision, rounding)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn tick_index_to_price(
+    tick: i32,
+    decimals_a: u8,
+    decimals_b: u8,
+    precision: u8,
+    rounding: Rounding,
+) -> String {
+    sqrt_price_to_price_x128(get_sqrt_price_at_tick(tick))
+        .to_ui_decimal(decimals_a, decimals_b, precision, rounding)
+}
+
+// Largest tick whose price is <= price
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn price_to_tick_index(price: String, decimals_a: u8, decimals_b: u8) -> i32 {
+    get_tick_at_sqrt_price(get_sqrt_price_from_price(&price, decimals_a, decimals_b))
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn price_to_initializable_tick_index(
+    price: String,
+    decimals_a: u8,
+    decimals_b: u8,
+    tick_spacing: u32,
+) -> i32 {
+    get_initializable_tick_index(
+        price_to_tick_index(price, decimals_a, decimals_b),
+        tick_spacing,
+    )
+}
+
+// Rounds toward zero as the SDK does, so a negative tick goes up to the multiple above it
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_initializable_tick_index(tick: i32, tick_spacing: u32) -> i32 {
+    assert!(tick_spacing > 0, "ErrInvalidTickSpacing");
+    tick - tick % tick_spacing as i32
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_next_initializable_tick_index(tick: i32, tick_spacing: u32) -> i32 {
+    get_initializable_tick_index(tick, tick_spacing) + tick_spacing as i32
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_prev_initializable_tick_index(tick: i32, tick_spacing: u32) -> i32 {
+    get_initializable_tick_index(tick, tick_spacing) - tick_spacing as i32
+}
+
+// Lowest tick a position can use with the tick spacing
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_min_tick(tick_spacing: u32) -> i32 {
+    get_initializable_tick_index(MIN_TICK, tick_spacing)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_max_tick(tick_spacing: u32) -> i32 {
+    get_initializable_tick_index(MAX_TICK, tick_spacing)
+}
+
+// Native version of tick_index_to_sqrt_price_x64
+pub fn get_sqrt_price_at_tick(tick: i32) -> u128 {
+    assert!((MIN_TICK..=MAX_TICK).contains(&tick), "ErrInvalidTick");
+    if tick > 0 {
+        get_sqrt_price_at_positive_tick(tick as u32)
+    } else {
+        get_sqrt_price_at_negative_tick(tick.unsigned_abs())
+    }
+}
+
+// Native version of sqrt_price_x64_to_tick_index
+pub fn get_tick_at_sqrt_price(sqrt_price: u128) -> i32 {
+    assert!(
+        (MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price),
+        "ErrInvalidSqrtPrice"
+    );
+    let msb = 127 - sqrt_price.leading_zeros();
+    let log2p_integer_x32 = (msb as i128 - 64) << 32;
+
+    // r in [2^63, 2^64), squared BIT_PRECISION times for the fractional bits of log2
+    let mut r = if msb >= 64 {
+        sqrt_price >> (msb - 63)
+    } else {
+        sqrt_price << (63 - msb)
+    };
+    let mut bit: u128 = 1 << 63;
+    let mut log2p_fraction_x64: u128 = 0;
+    for _ in 0..BIT_PRECISION {
+        r *= r;
+        let r_more_than_two = r >> 127;
+        r >>= 63 + r_more_than_two;
+        log2p_fraction_x64 += bit * r_more_than_two;
+        bit >>= 1;
+    }
+
+    let log2p_x32 = log2p_integer_x32 + (log2p_fraction_x64 >> 32) as i128;
+    let logbp_x64 = log2p_x32 * LOG_B_2_X32;
+    let tick_low = ((logbp_x64 - LOG_B_P_ERR_MARGIN_LOWER_X64) >> 64) as i32;
+    let tick_high = ((logbp_x64 + LOG_B_P_ERR_MARGIN_UPPER_X64) >> 64) as i32;
+    if tick_low == tick_high || get_sqrt_price_at_tick(tick_high) > sqrt_price {
+        tick_low
+    } else {
+        tick_high
+    }
+}
+
+// Native version of price_to_sqrt_price_x64: sqrt(price * 2^128), rounded down
+pub fn get_sqrt_price_from_price(price: &str, decimals_a: u8, decimals_b: u8) -> u128 {
+    let price = PriceX128::try_from_ui_decimal(price, decimals_a, decimals_b, Rounding::Down)
+        .expect("ErrPriceOverflow");
+    // floor(sqrt(floor(v))) == floor(sqrt(v))
+    price.x128().root(2).to::<u128>()
+}
+
+// Exact, sqrt_price^2 is the price as a 128.128 number
+pub fn sqrt_price_to_price_x128(sqrt_price: u128) -> PriceX128 {
+    let sqrt_price = U256::from(sqrt_price);
+    PriceX128::from_x128(sqrt_price * sqrt_price)
+}
+
+fn get_sqrt_price_at_positive_tick(tick: u32) -> u128 {
+    let mut ratio = if tick & 1 != 0 {
+        U256::from(79232123823359799118286999567u128)
+    } else {
+        U256::from(79228162514264337593543950336u128)
+    };
+    for (i, &multiplier) in POSITIVE_RATIOS_X96.iter().enumerate() {
+        if tick & (2 << i) != 0 {
+            ratio = (ratio * U256::from(multiplier)) >> 96;
+        };
+    }
+    let sqrt_price: U256 = ratio >> 32;
+    sqrt_price.to::<u128>()
+}
+
+fn get_sqrt_price_at_negative_tick(tick: u32) -> u128 {
+    let mut ratio: u128 = if tick & 1 != 0 {
+        18445821805675392311
+    } else {
+        18446744073709551616
+    };
+    for (i, &multiplier) in NEGATIVE_RATIOS_X64.iter().enumerate() {
+        if tick & (2 << i) != 0 {
+            // both < 2^64
+            ratio = (ratio * multiplier) >> 64;
+        };
+    }
+    ratio
+}
+
+#[test]
+fn test_sqrt_price_at_tick() {
+    assert!(get_sqrt_price_at_tick(0) == 1 << 64);
+    assert!(get_sqrt_price_at_tick(MIN_TICK) == MIN_SQRT_PRICE_X64);
+    assert!(get_sqrt_price_at_tick(MAX_TICK) == MAX_SQRT_PRICE_X64);
+    // values of TickMath.tickIndexToSqrtPriceX64
+    assert!(get_sqrt_price_at_tick(1) == 18447666387855959850);
+    assert!(get_sqrt_price_at_tick(-1) == 18445821805675392311);
+    assert!(tick_index_to_sqrt_price_x64(MAX_TICK) == "79226673515401279992447579055");
+}
+
+#[test]
+fn test_tick_at_sqrt_price() {
+    assert!(get_tick_at_sqrt_price(MIN_SQRT_PRICE_X64) == MIN_TICK);
+    assert!(get_tick_at_sqrt_price(MAX_SQRT_PRICE_X64) == MAX_TICK);
+    assert!(get_tick_at_sqrt_price(1 << 64) == 0);
+    assert!(sqrt_price_x64_to_tick_index("18446744073709551615".to_string()) == -1);
+
+    let ticks = (MIN_TICK..=MAX_TICK).step_by(997).chain([-2, -1, 1, 2]);
+    for tick in ticks {
+        let sqrt_price = get_sqrt_price_at_tick(tick);
+        assert!(get_tick_at_sqrt_price(sqrt_price) == tick);
+        if tick > MIN_TICK {
+            assert!(get_tick_at_sqrt_price(sqrt_price - 1) == tick - 1);
+        };
+        if tick < MAX_TICK {
+            assert!(get_tick_at_sqrt_price(sqrt_price + 1) == tick);
+        };
+    }
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidSqrtPrice")]
+fn test_tick_at_sqrt_price_out_of_range() {
+    get_tick_at_sqrt_price(MAX_SQRT_PRICE_X64 + 1);
+}
+
+#[test]
+fn test_price_conversions() {
+    // 1 SUI (9 decimals) = 3.5 USDC (6 decimals)
+    let sqrt_price = get_sqrt_price_from_price("3.5", 9, 6);
+    let price = sqrt_price_x64_to_price(sqrt_price.to_string(), 9, 6, 6, Rounding::Nearest);
+    assert!(price == "3.500000");
+    let tick = price_to_tick_index("3.5".to_string(), 9, 6);
+    assert!(
+        get_sqrt_price_at_tick(tick) <= sqrt_price && sqrt_price < get_sqrt_price_at_tick(tick + 1)
+    );
+    assert!(tick_index_to_price(0, 6, 6, 2, Rounding::Down) == "1.00");
+    assert!(price_to_tick_index("1".to_string(), 6, 6) == 0);
+    // 1.0001^-1 is below 0.99995
+    assert!(price_to_tick_index("0.99995".to_string(), 6, 6) == -1);
+    assert!(
+        price_to_initializable_tick_index("3.5".to_string(), 9, 6, 60)
+            == get_initializable_tick_index(tick, 60)
+    );
+}
+
+#[test]
+fn test_initializable_ticks() {
+    assert!(get_initializable_tick_index(125, 60) == 120);
+    assert!(get_initializable_tick_index(-125, 60) == -120);
+    assert!(get_next_initializable_tick_index(125, 60) == 180);
+    assert!(get_prev_initializable_tick_index(-125, 60) == -180);
+    assert!(get_min_tick(60) == -443580 && get_max_tick(60) == 443580);
+    assert!(get_min_tick(1) == MIN_TICK);
+}
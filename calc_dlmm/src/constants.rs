@@ -1,6 +1,7 @@
-use alloy_primitives::U256;
+use alloy_primitives::{U256, uint};
 
 pub const SCALE_OFFSET: u8 = 128;
+pub const SCALE: U256 = U256::from_limbs([0, 0, 1, 0]); // 1 << SCALE_OFFSET
 
 pub const PRECISION_N: u8 = 9;
 pub const PRECISION: u128 = 1000000000;
@@ -12,8 +13,11 @@ pub const MAX_PROTOCOL_SHARE: u16 = 2500; // 25%
 
 pub const DAY: u64 = 86400;
 
+pub const MAX_LIQUIDITY_PER_BIN: U256 =
+    uint!(65251743116719673010965625540244653191619923014385985379600384103134737_U256);
+
 pub fn scale() -> U256 {
-    U256::from(1) << SCALE_OFFSET
+    SCALE
 }
 
 pub fn scale_offset() -> u8 {
@@ -33,9 +37,18 @@ pub fn precision_n() -> u8 {
 }
 
 pub fn max_liquidity_per_bin() -> U256 {
-    U256::from_str_radix(
-        "65251743116719673010965625540244653191619923014385985379600384103134737",
-        10,
-    )
-    .unwrap()
+    MAX_LIQUIDITY_PER_BIN
+}
+
+#[test]
+fn test_constants() {
+    assert!(SCALE == U256::from(1) << SCALE_OFFSET);
+    assert!(
+        MAX_LIQUIDITY_PER_BIN
+            == U256::from_str_radix(
+                "65251743116719673010965625540244653191619923014385985379600384103134737",
+                10,
+            )
+            .unwrap()
+    );
 }
@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    constants,
+    price::{self, Rounding},
+    swap_result::DlmmPairParameter,
+    units::{Fee1e9, PriceX128},
+    validate::{self, Violation},
+};
+
+// What create_pair would start from: the active bin closest to the initial price, the
+// parameters of the new pair and the fees they imply
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairCreation {
+    pub storage_id: u32,
+    pub real_id: i32,
+    params: DlmmPairParameter,
+    base_fee: Fee1e9,
+    max_fee: Fee1e9,
+    violations: Vec<Violation>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PairCreation {
+    // 1e9 precision, as the create_pair base_fee argument
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn base_fee(&self) -> u64 {
+        self.base_fee.get()
+    }
+
+    // Total fee with the volatility accumulator at its maximum, 1e9 precision
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_fee(&self) -> u64 {
+        self.max_fee.get()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<String> {
+        self.violations.iter().map(Violation::to_string).collect()
+    }
+}
+
+impl PairCreation {
+    // Parameters of the new pair, with the volatility state zeroed and the active bin set
+    pub fn params(&self) -> &DlmmPairParameter {
+        &self.params
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
+
+// Arguments of the create_pair call
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairCreationInput {
+    // decimal string of token Y per token X in UI units, as priceTokenBPerTokenA
+    pub price: String,
+    pub decimals_x: u8,
+    pub decimals_y: u8,
+    pub bin_step: u16,
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub protocol_share: u16,
+    pub max_volatility_accumulator: u32,
+}
+
+// PairCreationInput as JSON
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn get_pair_creation_json(json: &str) -> Result<PairCreation, JsError> {
+    let input: PairCreationInput = serde_json::from_str(json)?;
+    Ok(get_pair_creation(&input))
+}
+
+pub fn get_pair_creation(input: &PairCreationInput) -> PairCreation {
+    let price = PriceX128::try_from_ui_decimal(
+        &input.price,
+        input.decimals_x,
+        input.decimals_y,
+        Rounding::Nearest,
+    )
+    .expect("ErrPriceOverflow");
+    let params = DlmmPairParameter {
+        base_factor: input.base_factor,
+        filter_period: input.filter_period,
+        decay_period: input.decay_period,
+        reduction_factor: input.reduction_factor,
+        variable_fee_control: input.variable_fee_control,
+        protocol_share: input.protocol_share,
+        max_volatility_accumulator: input.max_volatility_accumulator,
+        ..Default::default()
+    };
+    create_pair(price, input.bin_step, &params)
+}
+
+// Only the static fee parameters of `params` are used
+pub fn create_pair(price: PriceX128, bin_step: u16, params: &DlmmPairParameter) -> PairCreation {
+    assert!(
+        bin_step > 0 && bin_step <= constants::BASIS_POINT_MAX,
+        "ErrInvalidBinStep"
+    );
+    // the ids get_max_real_id allows span every non-zero price
+    assert!(price > PriceX128::ZERO, "ErrPriceOutOfRange");
+    let real_id = price::get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Nearest);
+    let storage_id = price::get_storage_id_from_real_id(real_id);
+
+    let params = DlmmPairParameter {
+        base_factor: params.base_factor,
+        filter_period: params.filter_period,
+        decay_period: params.decay_period,
+        reduction_factor: params.reduction_factor,
+        variable_fee_control: params.variable_fee_control,
+        protocol_share: params.protocol_share,
+        max_volatility_accumulator: params.max_volatility_accumulator,
+        index_reference: storage_id,
+        active_index: storage_id,
+        ..Default::default()
+    };
+
+    let mut violations = validate::validate_params(&params);
+    if params.filter_period > params.decay_period {
+        violations.push(Violation::ParamTooLarge {
+            field: "filter_period",
+            value: params.filter_period as u64,
+            max: params.decay_period as u64,
+        });
+    };
+
+    // saturates for parameters wider than their fields, reported above
+    let bounds = params.get_fee_bounds(bin_step);
+    if !bounds.is_within_max_fee() {
+        violations.push(Violation::FeeTooLarge {
+            fee: Fee1e9::new(bounds.max_fee()),
+        });
+    };
+
+    PairCreation {
+        storage_id,
+        real_id,
+        params,
+        base_fee: Fee1e9::new(bounds.base_fee()),
+        max_fee: Fee1e9::new(bounds.max_fee()),
+        violations,
+    }
+}
+
+// base_factor giving `base_fee` (1e9 precision) for the bin step, if there is one
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_base_factor_from_base_fee(base_fee: u64, bin_step: u16) -> Option<u16> {
+    if bin_step == 0 || !base_fee.is_multiple_of(bin_step as u64) {
+        return None;
+    };
+    u16::try_from(base_fee / bin_step as u64).ok()
+}
+
+#[cfg(test)]
+fn test_params() -> DlmmPairParameter {
+    DlmmPairParameter {
+        base_factor: 10000,
+        filter_period: 30,
+        decay_period: 600,
+        reduction_factor: 5000,
+        variable_fee_control: 40000,
+        protocol_share: 1000,
+        max_volatility_accumulator: 350000,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_create_pair() {
+    // 1 SUI (9 decimals) = 3.5 USDC (6 decimals)
+    let json = r#"{
+        "price": "3.5",
+        "decimals_x": 9,
+        "decimals_y": 6,
+        "bin_step": 25,
+        "base_factor": 10000,
+        "filter_period": 30,
+        "decay_period": 600,
+        "reduction_factor": 5000,
+        "variable_fee_control": 40000,
+        "protocol_share": 1000,
+        "max_volatility_accumulator": 350000
+    }"#;
+    let input: PairCreationInput = serde_json::from_str(json).unwrap();
+    let creation = get_pair_creation(&input);
+    let price = PriceX128::try_from_ui_decimal("3.5", 9, 6, Rounding::Nearest).unwrap();
+    assert!(
+        creation.real_id
+            == price::get_real_id_from_price_x128_rounded(price, 25, Rounding::Nearest)
+    );
+    assert!(creation.storage_id == price::get_storage_id_from_real_id(creation.real_id));
+    let bin_price = price::get_bin_price_x128(creation.real_id, 25);
+    assert!(bin_price.to_ui_decimal(9, 6, 2, Rounding::Nearest) == "3.50");
+
+    assert!(creation.is_valid() && creation.violations().is_empty());
+    assert!(creation.params().active_index == creation.storage_id);
+    assert!(creation.params().index_reference == creation.storage_id);
+    assert!(creation.base_fee() == 250_000);
+    // (350000 * 25)^2 * 40000 / 100 / 1e9 rounded up
+    assert!(creation.max_fee() == 250_000 + 30_625_000);
+}
+
+#[test]
+fn test_create_pair_reports_bad_params() {
+    let params = DlmmPairParameter {
+        filter_period: 700,
+        protocol_share: constants::MAX_PROTOCOL_SHARE + 1,
+        max_volatility_accumulator: (1 << 20) - 1,
+        variable_fee_control: (1 << 24) - 1,
+        ..test_params()
+    };
+    let creation = create_pair(PriceX128::ONE, 100, &params);
+    assert!(creation.real_id == 0 && !creation.is_valid());
+    assert!(
+        creation.violations()
+            == [
+                Violation::ParamTooLarge {
+                    field: "protocol_share",
+                    value: constants::MAX_PROTOCOL_SHARE as u64 + 1,
+                    max: constants::MAX_PROTOCOL_SHARE as u64,
+                },
+                Violation::ParamTooLarge {
+                    field: "filter_period",
+                    value: 700,
+                    max: 600,
+                },
+                Violation::FeeTooLarge {
+                    fee: Fee1e9::new(creation.max_fee()),
+                },
+            ]
+    );
+}
+
+#[test]
+fn test_create_pair_reports_out_of_width_params() {
+    let params = DlmmPairParameter {
+        variable_fee_control: u32::MAX,
+        max_volatility_accumulator: u32::MAX,
+        ..test_params()
+    };
+    let creation = create_pair(PriceX128::ONE, 100, &params);
+    assert!(!creation.is_valid() && creation.max_fee() == u64::MAX);
+    assert!(
+        creation.violations()
+            == [
+                Violation::ParamTooWide {
+                    field: "variable_fee_control",
+                    value: u32::MAX as u64,
+                    bits: 24,
+                },
+                Violation::ParamTooWide {
+                    field: "max_volatility_accumulator",
+                    value: u32::MAX as u64,
+                    bits: 20,
+                },
+                Violation::FeeTooLarge {
+                    fee: Fee1e9::new(u64::MAX),
+                },
+            ]
+    );
+}
+
+#[test]
+fn test_create_pair_price_bounds() {
+    let max_id = price::get_max_real_id(100);
+    // the lowest ids share the smallest price
+    let one = alloy_primitives::U256::from(1);
+    let lowest = create_pair(PriceX128::from_x128(one), 100, &test_params());
+    assert!(price::get_bin_price_x128(lowest.real_id, 100).x128() == one);
+    assert!(create_pair(PriceX128::MAX, 100, &test_params()).real_id == max_id);
+}
+
+#[test]
+#[should_panic(expected = "ErrPriceOutOfRange")]
+fn test_create_pair_price_out_of_range() {
+    create_pair(PriceX128::ZERO, 100, &test_params());
+}
+
+#[test]
+fn test_base_factor_from_base_fee() {
+    assert!(get_base_factor_from_base_fee(250_000, 25) == Some(10000));
+    assert!(get_base_factor_from_base_fee(250_001, 25).is_none());
+    assert!(get_base_factor_from_base_fee(25 << 16, 25).is_none());
+    assert!(get_base_factor_from_base_fee(1, 0).is_none());
+}
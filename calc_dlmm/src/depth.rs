@@ -0,0 +1,296 @@
+use alloy_primitives::{U256, U512};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    constants, price,
+    swap_result::{Bin, DlmmPair},
+    units::PriceX128,
+};
+
+// Order book view of a pair: the bins holding X are the asks (X sold for Y at or above the
+// active price), the bins holding Y are the bids. The active bin can be on both sides.
+// Sizes are in raw token units, the other token's amount is valued at the bin price.
+
+// One price level, one bin or a bucket of bins
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepthLevel {
+    // non-empty bins of the level
+    pub lower_real_id: i32,
+    pub upper_real_id: i32,
+    // price of the level's bin closest to the active one
+    price_x128: PriceX128,
+    pub amount_x: u128,
+    pub amount_y: u128,
+    // from the active bin to this level included
+    pub cumulative_x: u128,
+    pub cumulative_y: u128,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DepthLevel {
+    // 2^128 price as an int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn price_x128(&self) -> String {
+        self.price_x128.to_string()
+    }
+}
+
+impl DepthLevel {
+    pub fn price(&self) -> PriceX128 {
+        self.price_x128
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DepthProfile {
+    // best (highest) price first
+    bids: Vec<DepthLevel>,
+    // best (lowest) price first
+    asks: Vec<DepthLevel>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DepthProfile {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn bids(&self) -> Vec<DepthLevel> {
+        self.bids.clone()
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn asks(&self) -> Vec<DepthLevel> {
+        self.asks.clone()
+    }
+}
+
+// Liquidity within a price range around the active bin
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DepthRange {
+    // Y in the bids, and its value in X
+    pub bid_x: u128,
+    pub bid_y: u128,
+    // X in the asks, and its value in Y
+    pub ask_x: u128,
+    pub ask_y: u128,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    // Levels of `bins_per_level` bins, aligned on real ids so that they are stable across
+    // snapshots, 1 gives a level per bin
+    pub fn get_depth(&self, bins_per_level: u32) -> DepthProfile {
+        assert!(bins_per_level > 0, "ErrInvalidBinsPerLevel");
+        let (bids, asks) = self.sides();
+        DepthProfile {
+            bids: levels(&bids, self.bin_step(), bins_per_level, false),
+            asks: levels(&asks, self.bin_step(), bins_per_level, true),
+        }
+    }
+
+    // Depth with prices within +/- range_bps basis points of the active bin price
+    pub fn get_depth_within(&self, range_bps: u32) -> DepthRange {
+        let active_price = self.active_price();
+        // widened, the highest prices times the range overflow 256 bits
+        let offset = U512::from(active_price.x128()) * U512::from(range_bps)
+            / U512::from(constants::BASIS_POINT_MAX);
+        let offset = U256::saturating_from(offset);
+        let min_price = active_price.x128().saturating_sub(offset);
+        let max_price = active_price.x128().saturating_add(offset);
+
+        let bin_step = self.bin_step();
+        let (bids, asks) = self.sides();
+        let mut depth = DepthRange::default();
+        for bin in bids
+            .iter()
+            .take_while(|bin| bin_price(bin, bin_step).x128() >= min_price)
+        {
+            depth.bid_y += bin.reserve_y as u128;
+            depth.bid_x = depth.bid_x.saturating_add(value_in_x(bin, bin_step));
+        }
+        for bin in asks
+            .iter()
+            .take_while(|bin| bin_price(bin, bin_step).x128() <= max_price)
+        {
+            depth.ask_x += bin.reserve_x as u128;
+            depth.ask_y = depth.ask_y.saturating_add(value_in_y(bin, bin_step));
+        }
+        depth
+    }
+}
+
+impl DlmmPair {
+    fn active_price(&self) -> PriceX128 {
+        price::get_price_x128_from_storage_id(self.active_id(), self.bin_step())
+    }
+
+    // Non-empty bids from the active bin down and asks from the active bin up
+    fn sides(&self) -> (Vec<&Bin>, Vec<&Bin>) {
+        let active_id = self.active_id();
+        let mut bids: Vec<&Bin> = self
+            .bins()
+            .filter(|bin| bin.storage_id <= active_id && bin.reserve_y > 0)
+            .collect();
+        bids.sort_by_key(|bin| std::cmp::Reverse(bin.storage_id));
+        let mut asks: Vec<&Bin> = self
+            .bins()
+            .filter(|bin| bin.storage_id >= active_id && bin.reserve_x > 0)
+            .collect();
+        asks.sort_by_key(|bin| bin.storage_id);
+        (bids, asks)
+    }
+}
+
+fn levels(bins: &[&Bin], bin_step: u16, bins_per_level: u32, ask: bool) -> Vec<DepthLevel> {
+    let mut levels: Vec<DepthLevel> = Vec::new();
+    let mut current_bucket = None;
+    let (mut cumulative_x, mut cumulative_y) = (0u128, 0u128);
+    for bin in bins {
+        let real_id = price::get_real_id(bin.storage_id);
+        let (amount_x, amount_y) = if ask {
+            (bin.reserve_x as u128, value_in_y(bin, bin_step))
+        } else {
+            (value_in_x(bin, bin_step), bin.reserve_y as u128)
+        };
+        cumulative_x = cumulative_x.saturating_add(amount_x);
+        cumulative_y = cumulative_y.saturating_add(amount_y);
+
+        let bucket = real_id.div_euclid(bins_per_level as i32);
+        match levels.last_mut() {
+            Some(level) if current_bucket == Some(bucket) => {
+                level.lower_real_id = level.lower_real_id.min(real_id);
+                level.upper_real_id = level.upper_real_id.max(real_id);
+                level.amount_x = level.amount_x.saturating_add(amount_x);
+                level.amount_y = level.amount_y.saturating_add(amount_y);
+                level.cumulative_x = cumulative_x;
+                level.cumulative_y = cumulative_y;
+            }
+            _ => {
+                current_bucket = Some(bucket);
+                levels.push(DepthLevel {
+                    lower_real_id: real_id,
+                    upper_real_id: real_id,
+                    price_x128: bin_price(bin, bin_step),
+                    amount_x,
+                    amount_y,
+                    cumulative_x,
+                    cumulative_y,
+                });
+            }
+        };
+    }
+    levels
+}
+
+// The bin's price_q128 isn't always filled, zero in some snapshots, the price is derived
+// from its id
+fn bin_price(bin: &Bin, bin_step: u16) -> PriceX128 {
+    price::get_price_x128_from_storage_id(bin.storage_id, bin_step)
+}
+
+fn value_in_y(bin: &Bin, bin_step: u16) -> u128 {
+    saturating_u128(bin_price(bin, bin_step).x_to_y(bin.reserve_x))
+}
+
+fn value_in_x(bin: &Bin, bin_step: u16) -> u128 {
+    saturating_u128(bin_price(bin, bin_step).y_to_x(bin.reserve_y))
+}
+
+fn saturating_u128(value: U256) -> u128 {
+    u128::try_from(value).unwrap_or(u128::MAX)
+}
+
+#[test]
+fn test_depth_per_bin() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 10);
+    let depth = pair.get_depth(1);
+    // the active bin holds both tokens and is on both sides
+    assert!(depth.bids.len() == 11 && depth.asks.len() == 11);
+
+    let best_bid = depth.bids[0];
+    let best_ask = depth.asks[0];
+    assert!(best_bid.lower_real_id == 0 && best_ask.lower_real_id == 0);
+    assert!(best_bid.price() == PriceX128::ONE && best_ask.price() == PriceX128::ONE);
+    assert!(best_bid.amount_y == 1_000_000 && best_bid.amount_x == 1_000_000);
+
+    assert!(depth.bids.windows(2).all(|w| w[1].price() < w[0].price()));
+    assert!(depth.asks.windows(2).all(|w| w[1].price() > w[0].price()));
+    assert!(depth.bids.last().unwrap().cumulative_y == 11_000_000);
+    assert!(depth.asks.last().unwrap().cumulative_x == 11_000_000);
+    // X above the active price is worth more than 1 Y, Y below buys more than 1 X
+    assert!(depth.asks[1].amount_y > 1_000_000 && depth.bids[1].amount_x > 1_000_000);
+    let asks_y: u128 = depth.asks.iter().map(|level| level.amount_y).sum();
+    assert!(depth.asks.last().unwrap().cumulative_y == asks_y);
+}
+
+#[test]
+fn test_depth_buckets() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 10);
+    let per_bin = pair.get_depth(1);
+    let depth = pair.get_depth(4);
+    // asks: real ids 0..=10 in buckets [0, 3], [4, 7], [8, 10]
+    assert!(depth.asks.len() == 3);
+    assert!((depth.asks[1].lower_real_id, depth.asks[1].upper_real_id) == (4, 7));
+    assert!(depth.asks[1].amount_x == 4_000_000);
+    assert!(depth.asks[1].price() == price::get_bin_price_x128(4, 25));
+    // bids: real ids 0..=-10 in buckets [-4, -1] after [0, 3], the best price leads
+    assert!(depth.bids.len() == 4);
+    assert!((depth.bids[0].lower_real_id, depth.bids[0].upper_real_id) == (0, 0));
+    assert!((depth.bids[1].lower_real_id, depth.bids[1].upper_real_id) == (-4, -1));
+    assert!(depth.bids[1].price() == price::get_bin_price_x128(-1, 25));
+    assert!(depth.bids.last().unwrap().cumulative_x == per_bin.bids.last().unwrap().cumulative_x);
+}
+
+#[test]
+fn test_depth_within() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 10);
+    // +1% covers 3 steps of 0.25% up, 1.0025^4 > 1.01, and -1% 4 steps down, 1.0025^-4 > 0.99
+    let depth = pair.get_depth_within(100);
+    assert!(depth.bid_y == 5_000_000 && depth.ask_x == 4_000_000);
+    assert!(depth.ask_y > 4_000_000 && depth.bid_x > 5_000_000);
+    assert!(pair.get_depth_within(0).ask_x == 1_000_000);
+    assert!(pair.get_depth_within(u32::MAX).bid_y == 11_000_000);
+}
+
+#[test]
+fn test_depth_within_at_the_highest_prices() {
+    // X in the top bin, Y in the 4 bins below the run of ids priced at PriceX128::MAX
+    let max_id = price::get_max_real_id(25);
+    let (first, _) = price::get_ids_sharing_price(max_id, 25);
+    let top = price::get_storage_id_from_real_id(max_id);
+    let params = crate::swap_result::DlmmPairParameter {
+        active_index: top,
+        index_reference: top,
+        ..Default::default()
+    };
+    let mut bins: Vec<Bin> = (first - 4..first)
+        .map(|real_id| Bin {
+            storage_id: price::get_storage_id_from_real_id(real_id),
+            reserve_y: 1_000_000,
+            ..Default::default()
+        })
+        .collect();
+    bins.push(Bin {
+        storage_id: top,
+        reserve_x: 1_000_000,
+        ..Default::default()
+    });
+    // the prices near the top keep few significant bits, the ids past the run price at about a
+    // half of it. -60% reaches them, the top price times the range overflows 256 bits
+    let depth = DlmmPair::new(25, params, bins).get_depth_within(6_000);
+    assert!(depth.ask_x == 1_000_000 && depth.bid_y == 4_000_000);
+}
+
+#[test]
+fn test_depth_without_bin_prices() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 10);
+    let mut unpriced = pair.clone();
+    for bin in unpriced.bins.values_mut() {
+        bin.price_q128 = PriceX128::ZERO;
+    }
+    assert!(unpriced.get_depth(1) == pair.get_depth(1));
+    assert!(unpriced.get_depth_within(100) == pair.get_depth_within(100));
+}
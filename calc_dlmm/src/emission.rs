@@ -0,0 +1,285 @@
+use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    constants,
+    units::{int_string, int_string_vec},
+};
+
+// Forward projection of the minter's weekly emissions. Each epoch the emission decays by
+// weekly_decay_bps until it falls under tail_emission_threshold, then the minter freezes its
+// weekly amount and the emission is tail_rate_bps of the total supply from then on. On top of the emission, lockers get a rebase of
+// emission * (1 - locked / supply)^2 / 2 and the team team_rate_bps of everything minted.
+// The emission goes to the gauges pro rata of their votes, the rebase is locked.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmissionSchedule {
+    pub weekly_decay_bps: u16,
+    #[serde(with = "int_string")]
+    pub tail_emission_threshold: u128,
+    pub tail_rate_bps: u16,
+    pub team_rate_bps: u16,
+}
+
+// The SDK's EpochEmission, amounts as numbers or strings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochEmission {
+    #[serde(with = "int_string")]
+    pub emission: u128,
+    #[serde(with = "int_string")]
+    pub rebase: u128,
+    #[serde(with = "int_string")]
+    pub total_supply: u128,
+    #[serde(with = "int_string")]
+    pub total_locked: u128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmissionProjection {
+    pub schedule: EmissionSchedule,
+    pub current: EpochEmission,
+    // vote weight of each gauge
+    #[serde(with = "int_string_vec")]
+    pub votes: Vec<u128>,
+    pub epochs: u32,
+    // whether the minter already pays the tail emission
+    #[serde(default)]
+    pub tail: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectedEpoch {
+    // epochs after the current one, from 1
+    pub epoch: u32,
+    // supply and locked amounts once the epoch is minted
+    #[serde(flatten)]
+    pub emission: EpochEmission,
+    #[serde(with = "int_string")]
+    pub team: u128,
+    // emission of each gauge, in the order of the votes
+    #[serde(with = "int_string_vec")]
+    pub gauges: Vec<u128>,
+}
+
+// EmissionProjection as JSON, return the list of ProjectedEpoch as JSON
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn project_emissions_json(json: &str) -> Result<String, JsError> {
+    let projection: EmissionProjection = serde_json::from_str(json)?;
+    Ok(serde_json::to_string(&projection.run())?)
+}
+
+impl EmissionSchedule {
+    // Whether the epoch after `previous` pays the tail emission, `tail` whether `previous`
+    // did. The decayed emission isn't checked again once in the tail, which would otherwise
+    // swing back to the decay as soon as the tail emission grows past the threshold
+    pub fn pays_tail(&self, previous: &EpochEmission, tail: bool) -> bool {
+        tail || self.decayed(previous) < self.tail_emission_threshold
+    }
+
+    fn decayed(&self, previous: &EpochEmission) -> u128 {
+        previous.emission * self.weekly_decay_bps as u128 / constants::BASIS_POINT_MAX as u128
+    }
+
+    // Emission, rebase and team amounts minted the epoch after `previous`
+    pub fn next_epoch(&self, previous: &EpochEmission, tail: bool) -> (EpochEmission, u128) {
+        let bps_max = constants::BASIS_POINT_MAX as u128;
+        assert!(
+            self.weekly_decay_bps as u128 <= bps_max
+                && self.tail_rate_bps as u128 <= bps_max
+                && (self.team_rate_bps as u128) < bps_max,
+            "ErrInvalidEmissionSchedule"
+        );
+        assert!(
+            previous.total_locked <= previous.total_supply,
+            "ErrLockedAboveSupply"
+        );
+        let emission = if self.pays_tail(previous, tail) {
+            previous.total_supply * self.tail_rate_bps as u128 / bps_max
+        } else {
+            self.decayed(previous)
+        };
+        let rebase = if previous.total_supply == 0 {
+            0
+        } else {
+            let unlocked = U256::from(previous.total_supply - previous.total_locked);
+            let supply = U256::from(previous.total_supply);
+            (U256::from(emission) * unlocked * unlocked / (supply * supply) / U256::from(2))
+                .to::<u128>()
+        };
+        let team = (emission + rebase) * self.team_rate_bps as u128
+            / (bps_max - self.team_rate_bps as u128);
+        let next = EpochEmission {
+            emission,
+            rebase,
+            total_supply: previous.total_supply + emission + rebase + team,
+            total_locked: previous.total_locked + rebase,
+        };
+        (next, team)
+    }
+}
+
+impl EmissionProjection {
+    pub fn run(&self) -> Vec<ProjectedEpoch> {
+        let mut previous = self.current;
+        let mut tail = self.tail;
+        (1..=self.epochs)
+            .map(|epoch| {
+                tail = self.schedule.pays_tail(&previous, tail);
+                let (emission, team) = self.schedule.next_epoch(&previous, tail);
+                previous = emission;
+                ProjectedEpoch {
+                    epoch,
+                    emission,
+                    team,
+                    gauges: get_gauge_emissions(emission.emission, &self.votes),
+                }
+            })
+            .collect()
+    }
+}
+
+// Emission split pro rata of the votes, rounded down. Nothing goes out without votes
+pub fn get_gauge_emissions(emission: u128, votes: &[u128]) -> Vec<u128> {
+    let total_votes: u128 = votes.iter().sum();
+    votes
+        .iter()
+        .map(|&vote| {
+            if total_votes == 0 {
+                0
+            } else {
+                (U256::from(emission) * U256::from(vote) / U256::from(total_votes)).to::<u128>()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_next_epoch() {
+    let schedule = EmissionSchedule {
+        weekly_decay_bps: 9900,
+        tail_emission_threshold: 8_970_000,
+        tail_rate_bps: 67,
+        team_rate_bps: 500,
+    };
+    let previous = EpochEmission {
+        emission: 10_000_000,
+        rebase: 0,
+        total_supply: 1_000_000_000,
+        total_locked: 500_000_000,
+    };
+    let (next, team) = schedule.next_epoch(&previous, false);
+    // 1% decay, half the supply unlocked: 9_900_000 / 4 / 2
+    assert!(next.emission == 9_900_000 && next.rebase == 1_237_500);
+    // 5% of all minted
+    assert!(team == 586_184);
+    assert!(next.total_supply == 1_000_000_000 + 9_900_000 + 1_237_500 + 586_184);
+    assert!(next.total_locked == 501_237_500);
+
+    // under the threshold the tail takes over: 0.67% of the supply
+    let (tail, _) = schedule.next_epoch(
+        &EpochEmission {
+            emission: 9_000_000,
+            ..previous
+        },
+        false,
+    );
+    assert!(tail.emission == 6_700_000);
+    // everything locked, no rebase
+    let (locked, _) = schedule.next_epoch(
+        &EpochEmission {
+            total_locked: previous.total_supply,
+            ..previous
+        },
+        false,
+    );
+    assert!(locked.rebase == 0);
+}
+
+#[test]
+fn test_projection() {
+    let projection = EmissionProjection {
+        schedule: EmissionSchedule {
+            weekly_decay_bps: 9900,
+            tail_emission_threshold: 8_970_000,
+            tail_rate_bps: 67,
+            team_rate_bps: 500,
+        },
+        current: EpochEmission {
+            emission: 10_000_000,
+            rebase: 0,
+            total_supply: 1_000_000_000,
+            total_locked: 500_000_000,
+        },
+        votes: vec![3, 1, 0],
+        epochs: 20,
+        tail: false,
+    };
+    let epochs = projection.run();
+    assert!(epochs.len() == 20 && epochs[0].epoch == 1);
+    assert!(epochs[0].gauges == vec![7_425_000, 2_475_000, 0]);
+    assert!(
+        epochs
+            .windows(2)
+            .all(|w| w[1].emission.total_supply > w[0].emission.total_supply)
+    );
+    // the decay reaches the threshold after 11 epochs, the tail grows with the supply
+    assert!(epochs[9].emission.emission > epochs[10].emission.emission);
+    assert!(epochs[11].emission.emission < epochs[12].emission.emission);
+
+    let json = serde_json::to_string(&projection).unwrap();
+    let parsed: EmissionProjection = serde_json::from_str(&json).unwrap();
+    assert!(parsed == projection);
+    let epoch_json = serde_json::to_string(&epochs[0]).unwrap();
+    assert!(epoch_json.contains("\"emission\":\"9900000\",\"rebase\":\"1237500\""));
+}
+
+#[test]
+fn test_projection_stays_in_tail() {
+    // the tail of a 1.4e9 supply, 9_380_000, decays to more than the threshold
+    let projection = EmissionProjection {
+        schedule: EmissionSchedule {
+            weekly_decay_bps: 9900,
+            tail_emission_threshold: 8_970_000,
+            tail_rate_bps: 67,
+            team_rate_bps: 500,
+        },
+        current: EpochEmission {
+            emission: 9_000_000,
+            rebase: 0,
+            total_supply: 1_400_000_000,
+            total_locked: 1_400_000_000,
+        },
+        votes: vec![1],
+        epochs: 10,
+        tail: false,
+    };
+    let epochs = projection.run();
+    let mut supply = projection.current.total_supply;
+    for epoch in &epochs {
+        assert!(epoch.emission.emission == supply * 67 / 10_000);
+        supply = epoch.emission.total_supply;
+    }
+    // the tail carries over from a current epoch already in it
+    let from_tail = EmissionProjection {
+        current: epochs[0].emission,
+        tail: true,
+        ..projection.clone()
+    };
+    assert!(
+        from_tail.run()[0]
+            == ProjectedEpoch {
+                epoch: 1,
+                ..epochs[1].clone()
+            }
+    );
+    let json = r#"{"schedule":{"weekly_decay_bps":9900,"tail_emission_threshold":"8970000","tail_rate_bps":67,"team_rate_bps":500},"current":{"emission":"1","rebase":"0","total_supply":"1","total_locked":"0"},"votes":[],"epochs":1}"#;
+    assert!(
+        !serde_json::from_str::<EmissionProjection>(json)
+            .unwrap()
+            .tail
+    );
+}
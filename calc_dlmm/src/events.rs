@@ -0,0 +1,368 @@
+use alloy_primitives::{U256, U512, ruint::UintTryFrom};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    price,
+    swap_result::{Bin, DlmmPair, SwapOutResult, get_swap_out_bins},
+    units::{u32_number, u64_string},
+};
+
+// Pair events applied to a DlmmPair mirror, so that the pair state can be rebuilt from the
+// event stream. Amounts are u64 decimal strings in JSON as in the other SDK events.
+//
+// Accounting, per bin:
+// - a swap adds the amount in minus the fee to the reserve in and takes the amount out from
+//   the other reserve, the LP share of the fee (fee - protocol fee) goes to fee_x / fee_y
+// - fee_growth_x / fee_growth_y grow by the LP fee per unit of staked_liquidity (128.128),
+//   staking itself is not covered by these events
+// - mint and burn add and remove reserves, collecting fees takes them from fee_x / fee_y
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PairEvent {
+    Swap(EventSwap),
+    Mint(EventLiquidity),
+    Burn(EventLiquidity),
+    CollectFees(EventCollectFees),
+    ParamsChanged(EventParamsChanged),
+}
+
+// One swap, with what it did in each bin it crossed in order
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventSwap {
+    pub swap_for_y: bool,
+    #[serde(with = "u64_string")]
+    pub timestamp_ms: u64,
+    pub bins: Vec<SwapBin>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapBin {
+    #[serde(with = "u32_number")]
+    pub storage_id: u32,
+    // fee included
+    #[serde(with = "u64_string")]
+    pub amount_in: u64,
+    #[serde(with = "u64_string")]
+    pub amount_out: u64,
+    #[serde(with = "u64_string")]
+    pub fee: u64,
+    // part of the fee
+    #[serde(with = "u64_string")]
+    pub protocol_fee: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventLiquidity {
+    pub bins: Vec<BinAmounts>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCollectFees {
+    pub bins: Vec<BinAmounts>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinAmounts {
+    #[serde(with = "u32_number")]
+    pub storage_id: u32,
+    #[serde(with = "u64_string")]
+    pub amount_x: u64,
+    #[serde(with = "u64_string")]
+    pub amount_y: u64,
+}
+
+// The static fee parameters, the volatility state and active index are kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventParamsChanged {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub protocol_share: u16,
+    pub max_volatility_accumulator: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    // PairEvent as JSON
+    #[cfg(feature = "wasm")]
+    pub fn apply_event_json(&mut self, json: &str) -> Result<(), JsError> {
+        let event: PairEvent = serde_json::from_str(json)?;
+        self.apply_event(&event);
+        Ok(())
+    }
+}
+
+impl DlmmPair {
+    pub fn apply_event(&mut self, event: &PairEvent) {
+        match event {
+            PairEvent::Swap(swap) => self.apply_swap(swap),
+            PairEvent::Mint(mint) => self.apply_mint(mint),
+            PairEvent::Burn(burn) => self.apply_burn(burn),
+            PairEvent::CollectFees(collect) => self.apply_collect_fees(collect),
+            PairEvent::ParamsChanged(change) => self.apply_params_changed(change),
+        }
+    }
+
+    // The swap get_swap_out quotes, as the event the pair would emit for it
+    pub fn simulate_swap_out(
+        &self,
+        amount_in: u64,
+        swap_for_y: bool,
+        timestamp_ms: u64,
+    ) -> (SwapOutResult, EventSwap) {
+        let (result, bins) = get_swap_out_bins(self, amount_in, swap_for_y, timestamp_ms);
+        let event = EventSwap {
+            swap_for_y,
+            timestamp_ms,
+            bins,
+        };
+        (result, event)
+    }
+
+    pub fn apply_swap(&mut self, swap: &EventSwap) {
+        self.params.update_references(swap.timestamp_ms / 1000);
+        for step in &swap.bins {
+            self.params.update_volatility_accumulator(step.storage_id);
+            self.params.active_index = step.storage_id;
+
+            assert!(step.protocol_fee <= step.fee, "ErrInvalidProtocolFee");
+            let bin = self.bins.get_mut(&step.storage_id).expect("ErrBinNotFound");
+            let amount_in = step.amount_in.checked_sub(step.fee).expect("ErrInvalidFee");
+            let lp_fee = step.fee - step.protocol_fee;
+            let growth = fee_growth(lp_fee, bin.staked_liquidity);
+            if swap.swap_for_y {
+                bin.reserve_x = bin
+                    .reserve_x
+                    .checked_add(amount_in)
+                    .expect("ErrReserveOverflow");
+                bin.reserve_y = bin
+                    .reserve_y
+                    .checked_sub(step.amount_out)
+                    .expect("ErrInsufficientReserve");
+                bin.fee_x = bin.fee_x.checked_add(lp_fee).expect("ErrFeeOverflow");
+                bin.fee_growth_x = bin.fee_growth_x.wrapping_add(growth);
+            } else {
+                bin.reserve_y = bin
+                    .reserve_y
+                    .checked_add(amount_in)
+                    .expect("ErrReserveOverflow");
+                bin.reserve_x = bin
+                    .reserve_x
+                    .checked_sub(step.amount_out)
+                    .expect("ErrInsufficientReserve");
+                bin.fee_y = bin.fee_y.checked_add(lp_fee).expect("ErrFeeOverflow");
+                bin.fee_growth_y = bin.fee_growth_y.wrapping_add(growth);
+            };
+        }
+    }
+
+    // Creates the bins that don't exist yet
+    pub fn apply_mint(&mut self, mint: &EventLiquidity) {
+        let bin_step = self.bin_step;
+        for amounts in &mint.bins {
+            let bin = self.bins.entry(amounts.storage_id).or_insert_with(|| Bin {
+                storage_id: amounts.storage_id,
+                price_q128: price::get_price_x128_from_storage_id(amounts.storage_id, bin_step),
+                ..Default::default()
+            });
+            bin.reserve_x = bin
+                .reserve_x
+                .checked_add(amounts.amount_x)
+                .expect("ErrReserveOverflow");
+            bin.reserve_y = bin
+                .reserve_y
+                .checked_add(amounts.amount_y)
+                .expect("ErrReserveOverflow");
+        }
+    }
+
+    pub fn apply_burn(&mut self, burn: &EventLiquidity) {
+        for amounts in &burn.bins {
+            let bin = self
+                .bins
+                .get_mut(&amounts.storage_id)
+                .expect("ErrBinNotFound");
+            bin.reserve_x = bin
+                .reserve_x
+                .checked_sub(amounts.amount_x)
+                .expect("ErrInsufficientReserve");
+            bin.reserve_y = bin
+                .reserve_y
+                .checked_sub(amounts.amount_y)
+                .expect("ErrInsufficientReserve");
+        }
+    }
+
+    pub fn apply_collect_fees(&mut self, collect: &EventCollectFees) {
+        for amounts in &collect.bins {
+            let bin = self
+                .bins
+                .get_mut(&amounts.storage_id)
+                .expect("ErrBinNotFound");
+            bin.fee_x = bin
+                .fee_x
+                .checked_sub(amounts.amount_x)
+                .expect("ErrInsufficientFee");
+            bin.fee_y = bin
+                .fee_y
+                .checked_sub(amounts.amount_y)
+                .expect("ErrInsufficientFee");
+        }
+    }
+
+    pub fn apply_params_changed(&mut self, change: &EventParamsChanged) {
+        let params = &mut self.params;
+        params.base_factor = change.base_factor;
+        params.filter_period = change.filter_period;
+        params.decay_period = change.decay_period;
+        params.reduction_factor = change.reduction_factor;
+        params.variable_fee_control = change.variable_fee_control;
+        params.protocol_share = change.protocol_share;
+        params.max_volatility_accumulator = change.max_volatility_accumulator;
+    }
+}
+
+// lp_fee per unit of liquidity, 128.128. Only the counters wrap, a growth above 2^256 on
+// dust liquidity can't be added to them
+fn fee_growth(lp_fee: u64, staked_liquidity: U256) -> U256 {
+    if staked_liquidity == U256::ZERO {
+        return U256::ZERO;
+    };
+    let growth: U512 = (U512::from(lp_fee) << 256) / U512::from(staked_liquidity);
+    U256::uint_try_from(growth).expect("ErrFeeGrowthOverflow")
+}
+
+#[test]
+fn test_apply_swap_matches_quote() {
+    let mut pair = crate::swap_result::test_pair(25, 50);
+    let active_id = pair.active_id();
+    let before = pair.clone();
+
+    let (quote, event) = pair.simulate_swap_out(5_000_000, true, 1_000_000);
+    assert!(quote == crate::swap_result::get_swap_out(&pair, 5_000_000, true, 1_000_000));
+    assert!(quote.amount_in_left == 0 && quote.amount_out < 5_000_000);
+    assert!(event.bins.iter().map(|step| step.amount_in).sum::<u64>() == 5_000_000);
+    assert!(event.bins.iter().map(|step| step.amount_out).sum::<u64>() == quote.amount_out);
+    assert!(event.bins.iter().map(|step| step.fee).sum::<u64>() == quote.fee);
+    // selling X walks down through the bins below the active one
+    assert!(
+        event
+            .bins
+            .windows(2)
+            .all(|w| w[1].storage_id < w[0].storage_id)
+    );
+    assert!(event.bins[0].storage_id == active_id);
+
+    pair.apply_swap(&event);
+    let last = event.bins.last().unwrap();
+    assert!(pair.active_id() == last.storage_id);
+    assert!(pair.params().volatility_accumulator > 0);
+    let total = |pair: &DlmmPair, f: fn(&Bin) -> u64| pair.bins().map(f).sum::<u64>();
+    assert!(
+        total(&pair, |bin| bin.reserve_x)
+            == total(&before, |bin| bin.reserve_x) + 5_000_000 - quote.fee
+    );
+    assert!(
+        total(&pair, |bin| bin.reserve_y) == total(&before, |bin| bin.reserve_y) - quote.amount_out
+    );
+    let protocol_fee: u64 = event.bins.iter().map(|step| step.protocol_fee).sum();
+    assert!(protocol_fee > 0 && total(&pair, |bin| bin.fee_x) == quote.fee - protocol_fee);
+
+    // the same swap replayed from JSON
+    let mut replayed = before.clone();
+    let json = serde_json::to_string(&PairEvent::Swap(event)).unwrap();
+    replayed.apply_event(&serde_json::from_str(&json).unwrap());
+    assert!(replayed.params() == pair.params() && replayed.bins == pair.bins);
+}
+
+#[test]
+fn test_apply_liquidity_events() {
+    let mut pair = crate::swap_result::test_pair(25, 1);
+    let new_id = pair.active_id() + 5;
+    let mint = EventLiquidity {
+        bins: vec![
+            BinAmounts {
+                storage_id: pair.active_id(),
+                amount_x: 10,
+                amount_y: 20,
+            },
+            BinAmounts {
+                storage_id: new_id,
+                amount_x: 30,
+                amount_y: 0,
+            },
+        ],
+    };
+    pair.apply_event(&PairEvent::Mint(mint.clone()));
+    let bin = pair.bin(new_id).unwrap();
+    assert!(
+        bin.reserve_x == 30 && bin.price_q128 == price::get_price_x128_from_storage_id(new_id, 25)
+    );
+    let bin = pair.bin(pair.active_id()).unwrap();
+    assert!(bin.reserve_x == 1_000_010 && bin.reserve_y == 1_000_020);
+
+    pair.apply_event(&PairEvent::Burn(mint));
+    assert!(pair.bin(new_id).unwrap().reserve_x == 0);
+    assert!(pair.bin(pair.active_id()).unwrap().reserve_y == 1_000_000);
+
+    pair.bins.get_mut(&new_id).unwrap().fee_y = 7;
+    let collect = EventCollectFees {
+        bins: vec![BinAmounts {
+            storage_id: new_id,
+            amount_x: 0,
+            amount_y: 7,
+        }],
+    };
+    pair.apply_event(&PairEvent::CollectFees(collect));
+    assert!(pair.bin(new_id).unwrap().fee_y == 0);
+
+    let change = EventParamsChanged {
+        base_factor: 5000,
+        filter_period: 10,
+        decay_period: 120,
+        reduction_factor: 5000,
+        variable_fee_control: 0,
+        protocol_share: 2000,
+        max_volatility_accumulator: 100000,
+    };
+    let active_index = pair.active_id();
+    pair.apply_event(&PairEvent::ParamsChanged(change));
+    assert!(pair.params().base_factor == 5000 && pair.params().protocol_share == 2000);
+    assert!(pair.params().active_index == active_index);
+}
+
+#[test]
+#[should_panic(expected = "ErrInsufficientReserve")]
+fn test_apply_burn_too_much() {
+    let mut pair = crate::swap_result::test_pair(25, 1);
+    let burn = EventLiquidity {
+        bins: vec![BinAmounts {
+            storage_id: pair.active_id(),
+            amount_x: 1_000_001,
+            amount_y: 0,
+        }],
+    };
+    pair.apply_burn(&burn);
+}
+
+#[test]
+fn test_fee_growth() {
+    let one = U256::from(1) << 128;
+    assert!(fee_growth(5, U256::ZERO) == U256::ZERO);
+    // 5 fee over 2 units of liquidity
+    assert!(fee_growth(5, one * U256::from(2)) == (U256::from(5) << 128) / U256::from(2));
+    // a fee of 1 over the smallest liquidity not overflowing it
+    assert!(fee_growth(1, U256::from(2)) == U256::from(1) << 255);
+}
+
+#[test]
+#[should_panic(expected = "ErrFeeGrowthOverflow")]
+fn test_fee_growth_overflow() {
+    fee_growth(u64::MAX, U256::from(1));
+}
@@ -0,0 +1,139 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{constants, swap_result::DlmmPairParameter, units::Fee1e9};
+
+// Fee range of a parameter set, from the base fee of a quiet pair to the fee with the
+// volatility accumulator at its cap
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeBounds {
+    base_fee: Fee1e9,
+    max_variable_fee: Fee1e9,
+    max_fee: Fee1e9,
+    // bins to cross within one filter_period, starting from a zero volatility reference,
+    // for the volatility accumulator to reach max_volatility_accumulator
+    pub bins_to_max_fee: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FeeBounds {
+    // 1e9 precision
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn base_fee(&self) -> u64 {
+        self.base_fee.get()
+    }
+
+    // 1e9 precision
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_variable_fee(&self) -> u64 {
+        self.max_variable_fee.get()
+    }
+
+    // base_fee + max_variable_fee, 1e9 precision
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_fee(&self) -> u64 {
+        self.max_fee.get()
+    }
+
+    // Whether no swap can reach a fee above constants::MAX_FEE, where verify_fee panics
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn is_within_max_fee(&self) -> bool {
+        self.max_fee.is_valid()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_fee_bounds(
+    bin_step: u16,
+    base_factor: u16,
+    variable_fee_control: u32,
+    max_volatility_accumulator: u32,
+) -> FeeBounds {
+    let params = DlmmPairParameter {
+        base_factor,
+        variable_fee_control,
+        max_volatility_accumulator,
+        ..Default::default()
+    };
+    params.get_fee_bounds(bin_step)
+}
+
+impl DlmmPairParameter {
+    // Only the static fee parameters are used
+    pub fn get_fee_bounds(&self, bin_step: u16) -> FeeBounds {
+        let base_fee = self.get_base_fee(bin_step);
+        let max_variable_fee = self.get_variable_fee_at(bin_step, self.max_volatility_accumulator);
+        FeeBounds {
+            base_fee,
+            max_variable_fee,
+            max_fee: base_fee.saturating_add(max_variable_fee),
+            bins_to_max_fee: self
+                .max_volatility_accumulator
+                .div_ceil(constants::BASIS_POINT_MAX as u32),
+        }
+    }
+
+    // Variable fee for the given volatility accumulator, capped at max_volatility_accumulator.
+    // Parameters wider than their on-chain fields can give a fee past u64, it saturates to
+    // u64::MAX, far above constants::MAX_FEE
+    pub fn get_variable_fee_at(&self, bin_step: u16, volatility_accumulator: u32) -> Fee1e9 {
+        let volatility_accumulator = volatility_accumulator.min(self.max_volatility_accumulator);
+        Fee1e9::new(
+            self.variable_fee_1e9(bin_step, volatility_accumulator)
+                .saturating_to::<u64>(),
+        )
+    }
+
+    // Total fee after crossing `bins` bins within one filter_period from a quiet pair
+    pub fn get_total_fee_after_bins(&self, bin_step: u16, bins: u32) -> Fee1e9 {
+        let volatility_accumulator = bins.saturating_mul(constants::BASIS_POINT_MAX as u32);
+        self.get_base_fee(bin_step)
+            .saturating_add(self.get_variable_fee_at(bin_step, volatility_accumulator))
+    }
+}
+
+#[test]
+fn test_fee_bounds() {
+    let bounds = get_fee_bounds(25, 10000, 40000, 350000);
+    assert!(bounds.base_fee() == 250_000);
+    // (350000 * 25)^2 * 40000 / 100 / 1e9 rounded up
+    assert!(bounds.max_variable_fee() == 30_625_000);
+    assert!(bounds.max_fee() == 30_875_000 && bounds.is_within_max_fee());
+    assert!(bounds.bins_to_max_fee == 35);
+
+    let params = DlmmPairParameter {
+        base_factor: 10000,
+        variable_fee_control: 40000,
+        max_volatility_accumulator: 350000,
+        ..Default::default()
+    };
+    assert!(params.get_total_fee_after_bins(25, 0) == Fee1e9::new(250_000));
+    assert!(params.get_total_fee_after_bins(25, 34) < Fee1e9::new(bounds.max_fee()));
+    assert!(params.get_total_fee_after_bins(25, 35) == Fee1e9::new(bounds.max_fee()));
+    assert!(params.get_total_fee_after_bins(25, u32::MAX) == Fee1e9::new(bounds.max_fee()));
+
+    // matches what a swap sees with the accumulator at the cap
+    let capped = DlmmPairParameter {
+        volatility_accumulator: 350000,
+        ..params.clone()
+    };
+    assert!(capped.get_total_fee(25) == Fee1e9::new(bounds.max_fee()));
+}
+
+#[test]
+fn test_fee_bounds_above_max_fee() {
+    let bounds = get_fee_bounds(100, 10000, 100000, (1 << 20) - 1);
+    assert!(bounds.max_fee() > constants::MAX_FEE && !bounds.is_within_max_fee());
+    assert!(bounds.bins_to_max_fee == 105);
+
+    let quiet = get_fee_bounds(100, 10000, 0, (1 << 20) - 1);
+    assert!(quiet.max_variable_fee() == 0 && quiet.max_fee() == 1_000_000);
+}
+
+#[test]
+fn test_fee_bounds_out_of_width() {
+    // u32 fields wider than the 24 and 20 bits the pair packs them in
+    let bounds = get_fee_bounds(100, 0, u32::MAX, u32::MAX);
+    assert!(bounds.max_variable_fee() == u64::MAX && !bounds.is_within_max_fee());
+}
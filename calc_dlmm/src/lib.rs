@@ -1,6 +1,33 @@
+pub mod apr;
+pub mod backtest;
+pub mod bcs;
 mod bit_math;
-mod constants;
+pub mod clmm;
+pub mod constants;
+pub mod create_pair;
+pub mod depth;
+pub mod emission;
+pub mod events;
+pub mod fee_bounds;
+pub mod pool;
 pub mod price;
+pub mod price_table;
+pub mod router;
+pub mod summary;
 pub mod swap_result;
 mod u128x128;
 mod uint_safe;
+pub mod units;
+pub mod validate;
+pub mod ve;
+
+// Native API, the String based functions are the wasm bindings
+pub use price::{
+    BinPrice, DEFAULT_MAX_BINS, Rounding, get_bin_price_x128, get_bins_in_price_range,
+    get_max_real_id, get_price_x128_from_storage_id, get_real_id,
+    get_real_id_from_price_x128_rounded, get_storage_id_from_real_id,
+};
+pub use swap_result::{
+    Bin, DlmmPair, DlmmPairParameter, SwapInResult, SwapOutResult, get_swap_in, get_swap_out,
+};
+pub use units::{Fee1e9, PriceX128};
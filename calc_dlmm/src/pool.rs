@@ -0,0 +1,183 @@
+use crate::{
+    clmm::{pool::ClmmPool, tick_math},
+    price,
+    swap_result::{self, DlmmPair},
+    units::PriceX128,
+};
+
+// What a router needs from a pool, whatever its liquidity model. Token A is the first
+// token of the pool, X for a DLMM pair, and a2b sells it. The timestamp drives the dynamic
+// fee of DLMM pairs, CLMM pools ignore it
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolQuote {
+    // fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    // amount in, or amount out for an exact out quote, the pool couldn't fill
+    pub amount_left: u64,
+}
+
+pub trait Pool {
+    // Coin types of tokens A and B, empty when unknown
+    fn coin_types(&self) -> (&str, &str);
+
+    // Price of A in B, raw units
+    fn spot_price(&self) -> PriceX128;
+
+    fn quote_exact_in(&self, amount_in: u64, a2b: bool, timestamp_ms: u64) -> PoolQuote;
+
+    fn quote_exact_out(&self, amount_out: u64, a2b: bool, timestamp_ms: u64) -> PoolQuote;
+
+    // Apply the swap quote_exact_in quotes to the pool
+    fn swap_exact_in(&mut self, amount_in: u64, a2b: bool, timestamp_ms: u64) -> PoolQuote;
+
+    // Copy to simulate swaps on
+    fn clone_box(&self) -> Box<dyn Pool>;
+}
+
+impl Clone for Box<dyn Pool> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// A timestamp before the pair's last update counts as the last update
+impl Pool for DlmmPair {
+    fn coin_types(&self) -> (&str, &str) {
+        DlmmPair::coin_types(self)
+    }
+
+    fn spot_price(&self) -> PriceX128 {
+        price::get_price_x128_from_storage_id(self.active_id(), self.bin_step())
+    }
+
+    fn quote_exact_in(&self, amount_in: u64, a2b: bool, timestamp_ms: u64) -> PoolQuote {
+        let result = swap_result::get_swap_out(self, amount_in, a2b, timestamp_ms);
+        PoolQuote {
+            amount_in: amount_in - result.amount_in_left,
+            amount_out: result.amount_out,
+            fee: result.fee,
+            amount_left: result.amount_in_left,
+        }
+    }
+
+    fn quote_exact_out(&self, amount_out: u64, a2b: bool, timestamp_ms: u64) -> PoolQuote {
+        let result = swap_result::get_swap_in(self, amount_out, a2b, timestamp_ms);
+        PoolQuote {
+            amount_in: result.amount_in,
+            amount_out: amount_out - result.amount_out_left,
+            fee: result.fee,
+            amount_left: result.amount_out_left,
+        }
+    }
+
+    fn swap_exact_in(&mut self, amount_in: u64, a2b: bool, timestamp_ms: u64) -> PoolQuote {
+        let (result, event) = self.simulate_swap_out(amount_in, a2b, timestamp_ms);
+        self.apply_swap(&event);
+        PoolQuote {
+            amount_in: amount_in - result.amount_in_left,
+            amount_out: result.amount_out,
+            fee: result.fee,
+            amount_left: result.amount_in_left,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Pool> {
+        Box::new(self.clone())
+    }
+}
+
+// Quoted without a referrer and down to the min or max sqrt price
+impl Pool for ClmmPool {
+    fn coin_types(&self) -> (&str, &str) {
+        ClmmPool::coin_types(self)
+    }
+
+    fn spot_price(&self) -> PriceX128 {
+        tick_math::sqrt_price_to_price_x128(self.current_sqrt_price())
+    }
+
+    fn quote_exact_in(&self, amount_in: u64, a2b: bool, _timestamp_ms: u64) -> PoolQuote {
+        let result = self.swap(a2b, true, amount_in, None, 0);
+        PoolQuote {
+            amount_in: result.amount_in,
+            amount_out: result.amount_out,
+            fee: result.fee,
+            amount_left: result.amount_left,
+        }
+    }
+
+    fn quote_exact_out(&self, amount_out: u64, a2b: bool, _timestamp_ms: u64) -> PoolQuote {
+        let result = self.swap(a2b, false, amount_out, None, 0);
+        PoolQuote {
+            amount_in: result.amount_in,
+            amount_out: result.amount_out,
+            fee: result.fee,
+            amount_left: result.amount_left,
+        }
+    }
+
+    fn swap_exact_in(&mut self, amount_in: u64, a2b: bool, _timestamp_ms: u64) -> PoolQuote {
+        let result = self.swap(a2b, true, amount_in, None, 0);
+        self.apply_swap(&result);
+        PoolQuote {
+            amount_in: result.amount_in,
+            amount_out: result.amount_out,
+            fee: result.fee,
+            amount_left: result.amount_left,
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Pool> {
+        Box::new(self.clone())
+    }
+}
+
+#[test]
+fn test_pools_quote_and_swap() {
+    let mut pair = swap_result::test_pair(25, 50);
+    pair.set_coin_types("0x2::sui::SUI".to_string(), "0x5::usdc::USDC".to_string());
+    let mut clmm_pool = crate::clmm::pool::test_pool();
+    clmm_pool.set_coin_types("0x2::sui::SUI".to_string(), "0x5::usdc::USDC".to_string());
+    let pools: Vec<Box<dyn Pool>> = vec![Box::new(pair), Box::new(clmm_pool)];
+    for mut pool in pools {
+        assert!(pool.coin_types() == ("0x2::sui::SUI", "0x5::usdc::USDC"));
+        let price_before = pool.spot_price();
+        let quote = pool.quote_exact_in(1_000_000, true, 0);
+        assert!(quote.amount_in == 1_000_000 && quote.amount_left == 0);
+        assert!(quote.amount_out > 0 && quote.fee > 0);
+
+        let exact_out = pool.quote_exact_out(quote.amount_out, true, 0);
+        assert!(exact_out.amount_out == quote.amount_out && exact_out.amount_left == 0);
+        assert!(exact_out.amount_in.abs_diff(quote.amount_in) <= 2);
+
+        assert!(pool.swap_exact_in(1_000_000, true, 0) == quote);
+        assert!(pool.spot_price() <= price_before);
+        // the pool moved, the same amount now gets less
+        assert!(pool.quote_exact_in(1_000_000, true, 0).amount_out <= quote.amount_out);
+    }
+}
+
+#[test]
+fn test_pair_stale_timestamp() {
+    let mut pair = swap_result::test_pair(25, 50);
+    pair.params.time_of_last_update = 1_700_000_000;
+    let quote = pair.quote_exact_in(1_000_000, true, 0);
+    assert!(quote == pair.quote_exact_in(1_000_000, true, 1_700_000_000_000));
+    assert!(pair.swap_exact_in(1_000_000, true, 0) == quote);
+    assert!(pair.params().time_of_last_update == 1_700_000_000);
+}
+
+#[test]
+fn test_pools_spot_price() {
+    // both test pools sit at the price 1
+    let pools: Vec<Box<dyn Pool>> = vec![
+        Box::new(swap_result::test_pair(25, 50)),
+        Box::new(crate::clmm::pool::test_pool()),
+    ];
+    for pool in pools {
+        assert!(pool.spot_price() == PriceX128::ONE);
+    }
+}
@@ -1,14 +1,16 @@
-use alloy_primitives::U256;
-use std::str::FromStr;
+use alloy_primitives::{U256, U512};
+use std::{cell::RefCell, collections::HashMap, str::FromStr};
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use crate::{
     constants,
-    u128x128::{log2, pow, to_u128x128},
+    u128x128::{log2, pow, try_pow},
     uint_safe,
+    units::PriceX128,
 };
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 // Return 2^128 price
 pub fn get_price_x128_from_real_id(real_id: i32, bin_step: u16) -> String {
     let storage_id = get_storage_id_from_real_id(real_id);
@@ -18,16 +20,16 @@ pub fn get_price_x128_from_real_id(real_id: i32, bin_step: u16) -> String {
 
 const REAL_ID_SHIFT: u32 = 1 << 23;
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn get_storage_id_from_real_id(id: i32) -> u32 {
     if id >= 0i32 {
         id as u32 + REAL_ID_SHIFT
     } else {
-        REAL_ID_SHIFT - id.abs() as u32
+        REAL_ID_SHIFT - id.unsigned_abs()
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn get_real_id(storage_id: u32) -> i32 {
     assert!(storage_id < (REAL_ID_SHIFT << 1), "ErrStorageIDTooBig");
     if storage_id >= REAL_ID_SHIFT {
@@ -37,8 +39,8 @@ pub fn get_real_id(storage_id: u32) -> i32 {
     }
 }
 
-fn get_base(bin_step: u16) -> U256 {
-    U256::from(constants::scale())
+pub(crate) fn get_base(bin_step: u16) -> U256 {
+    constants::SCALE
         + (U256::from(bin_step) << constants::scale_offset())
             / U256::from(constants::basis_point_max())
 }
@@ -48,19 +50,226 @@ fn get_exponent(storage_id: u32) -> i32 {
 }
 
 // NOTE: price is a 128.128-binary fixed-point number
-pub fn get_price_x128_from_storage_id(storage_id: u32, bin_step: u16) -> U256 {
+pub fn get_price_x128_from_storage_id(storage_id: u32, bin_step: u16) -> PriceX128 {
     let base = get_base(bin_step);
     let exp = get_exponent(storage_id);
-    pow(base, exp)
+    PriceX128::from_x128(pow(base, exp))
 }
 
 // NOTE: price is a 128.128-binary fixed-point number
 //price_x2^128: is int string
-#[wasm_bindgen]
+// NOTE: truncates toward zero and may be one bin off near bin edges, see the
+// _floor / _ceil / _nearest variants for exact rounding
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn get_real_id_from_price_x128(price_x128: String, bin_step: u16) -> i32 {
-    let price = U256::from_str(&price_x128).unwrap();
+    let price = PriceX128::from_str(&price_x128).unwrap();
+    estimate_real_id_from_price_x128(price, bin_step)
+}
+
+// pow, as the Move u128x128 module, aborts for exponents of 2^20 and more
+const MAX_POW_EXPONENT: i32 = (1 << 20) - 1;
+
+thread_local! {
+    static MAX_REAL_IDS: RefCell<HashMap<u16, i32>> = RefCell::new(HashMap::new());
+}
+
+// Largest id the pair can price for the bin step, the valid real ids are [-max, max] and the
+// valid storage ids are REAL_ID_SHIFT +/- max. It is the largest exponent pow accepts whose
+// price doesn't underflow, the same on both sides. Near -max the 128.128 prices keep few
+// significant bits, and near max so does the reciprocal pow takes them from, so runs of
+// neighbouring ids share a price at both ends: the price maps back to the last id of the
+// run rounding down or to the nearest, and to the first one rounding up. Computed once per
+// bin step
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_max_real_id(bin_step: u16) -> i32 {
+    assert!(bin_step > 0, "ErrInvalidBinStep");
+    if let Some(max_id) = MAX_REAL_IDS.with(|ids| ids.borrow().get(&bin_step).copied()) {
+        return max_id;
+    };
+
+    // the underflow is monotonic in the exponent, binary search the last id that prices
+    let base = get_base(bin_step);
+    let (mut low, mut high) = (0, MAX_POW_EXPONENT);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if try_pow(base, -mid).is_some() {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    MAX_REAL_IDS.with(|ids| ids.borrow_mut().insert(bin_step, low));
+    low
+}
+
+// Largest id whose price is <= price_x128
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_real_id_from_price_x128_floor(price_x128: String, bin_step: u16) -> i32 {
+    let price = PriceX128::from_str(&price_x128).unwrap();
+    get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Down)
+}
+
+// Smallest id whose price is >= price_x128
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_real_id_from_price_x128_ceil(price_x128: String, bin_step: u16) -> i32 {
+    let price = PriceX128::from_str(&price_x128).unwrap();
+    get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Up)
+}
+
+// Id whose price is closest to price_x128 on a log scale
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_real_id_from_price_x128_nearest(price_x128: String, bin_step: u16) -> i32 {
+    let price = PriceX128::from_str(&price_x128).unwrap();
+    get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Nearest)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinPrice {
+    pub storage_id: u32,
+    pub real_id: i32,
+    price_x128: PriceX128,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BinPrice {
+    // 2^128 price as an int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn price_x128(&self) -> String {
+        self.price_x128.to_string()
+    }
+}
+
+impl BinPrice {
+    pub fn price(&self) -> PriceX128 {
+        self.price_x128
+    }
+}
+
+// Bins returned when no max_bins is given, the whole id space is ~1.8M bins at bin step 1
+pub const DEFAULT_MAX_BINS: u32 = 10_000;
+
+// Return the bins whose price lies in [lower, upper], clamped to the supported id space and
+// capped to the first `max_bins` bins from the lower end, DEFAULT_MAX_BINS by default
+// lower_price_x128, upper_price_x128: are int strings
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_bins_in_price_range_x128(
+    lower_price_x128: String,
+    upper_price_x128: String,
+    bin_step: u16,
+    max_bins: Option<u32>,
+) -> Vec<BinPrice> {
+    let lower = PriceX128::from_str(&lower_price_x128).unwrap();
+    let upper = PriceX128::from_str(&upper_price_x128).unwrap();
+    get_bins_in_price_range(lower, upper, bin_step, max_bins)
+}
+
+// Same as get_bins_in_price_range_x128, with decimal prices of token Y per token X in UI units
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_bins_in_decimal_price_range(
+    lower_price: String,
+    upper_price: String,
+    decimals_x: u8,
+    decimals_y: u8,
+    bin_step: u16,
+    max_bins: Option<u32>,
+) -> Vec<BinPrice> {
+    let Some(lower) =
+        PriceX128::try_from_ui_decimal(&lower_price, decimals_x, decimals_y, Rounding::Up)
+    else {
+        return Vec::new();
+    };
+    let upper =
+        PriceX128::try_from_ui_decimal(&upper_price, decimals_x, decimals_y, Rounding::Down)
+            .unwrap_or(PriceX128::MAX);
+    get_bins_in_price_range(lower, upper, bin_step, max_bins)
+}
+
+pub fn get_bins_in_price_range(
+    lower: PriceX128,
+    upper: PriceX128,
+    bin_step: u16,
+    max_bins: Option<u32>,
+) -> Vec<BinPrice> {
+    let max_id = get_max_real_id(bin_step);
+    let min_price = get_bin_price_x128(-max_id, bin_step);
+    let max_price = get_bin_price_x128(max_id, bin_step);
+    if lower > upper || upper < min_price || lower > max_price {
+        return Vec::new();
+    };
+
+    let first = get_real_id_from_price_x128_rounded(lower.max(min_price), bin_step, Rounding::Up);
+    let last = get_real_id_from_price_x128_rounded(upper.min(max_price), bin_step, Rounding::Down);
+    let max_bins = max_bins.unwrap_or(DEFAULT_MAX_BINS) as i64;
+    let last = (last as i64).min(first as i64 + max_bins - 1) as i32;
+
+    (first..=last)
+        .map(|real_id| BinPrice {
+            storage_id: get_storage_id_from_real_id(real_id),
+            real_id,
+            price_x128: get_bin_price_x128(real_id, bin_step),
+        })
+        .collect()
+}
+
+// price: decimal with constants::PRECISION decimals
+pub fn convert_decimal_price_to_128x128(price: U256) -> PriceX128 {
+    PriceX128::from_precision_decimal(price)
+}
+
+// Return a decimal with constants::PRECISION decimals
+pub fn convert_128x128_price_to_decimal(price: PriceX128) -> U256 {
+    price.to_precision_decimal()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    // Largest id whose price is <= the given price
+    Down,
+    // Smallest id whose price is >= the given price
+    Up,
+    // Closest id on a log scale, ties go up
+    Nearest,
+}
+
+// price: decimal string of token Y per token X in UI units, e.g. "1.2345" or "3e-7"
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_real_id_from_price(
+    price: String,
+    decimals_x: u8,
+    decimals_y: u8,
+    bin_step: u16,
+    rounding: Rounding,
+) -> i32 {
+    let price_x128 = PriceX128::try_from_ui_decimal(&price, decimals_x, decimals_y, rounding)
+        .expect("ErrPriceOverflow");
+    get_real_id_from_price_x128_rounded(price_x128, bin_step, rounding)
+}
+
+// Return the price of the bin as a decimal string of token Y per token X in UI units,
+// with exactly `precision` fractional digits
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_price_from_real_id(
+    real_id: i32,
+    bin_step: u16,
+    decimals_x: u8,
+    decimals_y: u8,
+    precision: u8,
+    rounding: Rounding,
+) -> String {
+    get_bin_price_x128(real_id, bin_step).to_ui_decimal(decimals_x, decimals_y, precision, rounding)
+}
+
+// Native version of get_price_x128_from_real_id
+pub fn get_bin_price_x128(real_id: i32, bin_step: u16) -> PriceX128 {
+    PriceX128::from_x128(pow(get_base(bin_step), real_id))
+}
+
+// Truncating estimate from the log2 ratio, may be off by one near bin edges
+fn estimate_real_id_from_price_x128(price: PriceX128, bin_step: u16) -> i32 {
     let base = get_base(bin_step);
-    let (price_abs, price_positive) = log2(price);
+    let (price_abs, price_positive) = log2(price.x128());
     let (base_abs, base_positive) = log2(base);
     let real_id_abs = uint_safe::safe32(price_abs / base_abs);
 
@@ -71,16 +280,63 @@ pub fn get_real_id_from_price_x128(price_x128: String, bin_step: u16) -> i32 {
     }
 }
 
-pub fn convert_decimal_price_to_128x128(price: U256) -> U256 {
-    to_u128x128(uint_safe::safe128(price), constants::precision_n())
-}
+pub fn get_real_id_from_price_x128_rounded(
+    price: PriceX128,
+    bin_step: u16,
+    rounding: Rounding,
+) -> i32 {
+    assert!(price > PriceX128::ZERO, "ErrPriceUnderflow");
+    let max_id = get_max_real_id(bin_step);
+
+    // the log2 estimate truncates toward zero and drops a bit, correct it against the bin prices
+    let mut id = estimate_real_id_from_price_x128(price, bin_step).clamp(-max_id, max_id);
+    while id > -max_id && get_bin_price_x128(id, bin_step) > price {
+        id -= 1;
+    }
+    while id < max_id && get_bin_price_x128(id + 1, bin_step) <= price {
+        id += 1;
+    }
 
-pub fn convert_128x128_price_to_decimal(price: U256) -> U256 {
-    (price * U256::from(constants::precision())) >> constants::scale_offset()
+    let lower = get_bin_price_x128(id, bin_step);
+    if lower == price {
+        // neighbouring ids can share a price near -max_id, round up to the smallest of them
+        if rounding == Rounding::Up {
+            while id > -max_id && get_bin_price_x128(id - 1, bin_step) == price {
+                id -= 1;
+            }
+        };
+        return id;
+    };
+    if lower > price {
+        // below the lowest representable bin
+        assert!(rounding != Rounding::Down, "ErrPriceOutOfRange");
+        return id;
+    };
+    if id == max_id {
+        // above the highest representable bin
+        assert!(rounding != Rounding::Up, "ErrPriceOutOfRange");
+        return id;
+    };
+    match rounding {
+        Rounding::Down => id,
+        Rounding::Up => id + 1,
+        Rounding::Nearest => {
+            // compare on a log scale: price^2 against lower * upper
+            let upper = get_bin_price_x128(id + 1, bin_step);
+            let price = U512::from(price.x128());
+            if price * price < U512::from(lower.x128()) * U512::from(upper.x128()) {
+                id
+            } else {
+                id + 1
+            }
+        }
+    }
 }
 
 #[test]
 fn test_get_base() {
+    use crate::u128x128::to_u128x128;
+
     assert!(to_u128x128(10001, 4) == get_base(1));
     assert!(to_u128x128(10005, 4) == get_base(5));
 }
@@ -101,16 +357,233 @@ fn test_invalid_storage_id() {
 
 #[test]
 fn test_get_storage_id() {
-    assert!(get_storage_id_from_real_id(i32::from(1)) == (1 + REAL_ID_SHIFT));
+    assert!(get_storage_id_from_real_id(1) == (1 + REAL_ID_SHIFT));
     assert!(get_storage_id_from_real_id(0i32) == REAL_ID_SHIFT);
     assert!(get_storage_id_from_real_id(i32_neg_from(1)) == (REAL_ID_SHIFT - 1));
     assert!(get_storage_id_from_real_id(7787) == 8396395);
     assert!(
         get_price_x128_from_storage_id(8391240, 50).to_string()
-            == "170967668064246121537697181132590453180080978".to_string()
+            == "170967668064246121537697181132590453180080978"
+    );
+}
+
+#[test]
+fn test_get_real_id_from_price() {
+    assert!(get_real_id_from_price("1".to_string(), 9, 9, 10, Rounding::Down) == 0);
+    assert!(get_real_id_from_price("1".to_string(), 9, 9, 10, Rounding::Up) == 0);
+
+    // sqrt(1.001) ~= 1.0004999
+    assert!(get_real_id_from_price("1.0004".to_string(), 9, 9, 10, Rounding::Down) == 0);
+    assert!(get_real_id_from_price("1.0004".to_string(), 9, 9, 10, Rounding::Up) == 1);
+    assert!(get_real_id_from_price("1.0004".to_string(), 9, 9, 10, Rounding::Nearest) == 0);
+    assert!(get_real_id_from_price("1.0005".to_string(), 9, 9, 10, Rounding::Nearest) == 1);
+
+    assert!(get_real_id_from_price("0.9995".to_string(), 9, 9, 10, Rounding::Down) == -1);
+    assert!(get_real_id_from_price("0.9995".to_string(), 9, 9, 10, Rounding::Up) == 0);
+
+    // 1 X = 1 Y in UI units is 1000 raw Y per raw X
+    let raw = PriceX128::from_x128(U256::from(1000) << constants::SCALE_OFFSET);
+    let id = get_real_id_from_price("1".to_string(), 6, 9, 10, Rounding::Down);
+    assert!(get_bin_price_x128(id, 10) <= raw);
+    assert!(get_bin_price_x128(id + 1, 10) > raw);
+    assert!(get_real_id_from_price("1e-3".to_string(), 6, 6, 10, Rounding::Up) == -id);
+}
+
+#[test]
+fn test_get_price_from_real_id() {
+    assert!(get_price_from_real_id(0, 10, 9, 9, 4, Rounding::Down) == "1.0000");
+    assert!(get_price_from_real_id(1, 10, 9, 9, 6, Rounding::Down) == "1.000999");
+    assert!(get_price_from_real_id(1, 10, 9, 9, 6, Rounding::Up) == "1.001000");
+    assert!(get_price_from_real_id(1, 10, 9, 9, 6, Rounding::Nearest) == "1.001000");
+    assert!(get_price_from_real_id(0, 10, 6, 9, 2, Rounding::Down) == "0.00");
+    assert!(get_price_from_real_id(0, 10, 9, 6, 0, Rounding::Down) == "1000");
+
+    for id in [-7787, -1, 0, 1, 7787] {
+        let price = get_price_from_real_id(id, 25, 9, 6, 30, Rounding::Nearest);
+        assert!(get_real_id_from_price(price, 9, 6, 25, Rounding::Nearest) == id);
+    }
+}
+
+#[test]
+fn test_get_max_real_id() {
+    for bin_step in [1u16, 10, 100, constants::BASIS_POINT_MAX] {
+        let max_id = get_max_real_id(bin_step);
+        let base = get_base(bin_step);
+        assert!(try_pow(base, max_id).is_some() && try_pow(base, -max_id).is_some());
+        assert!(try_pow(base, max_id + 1).is_none() && try_pow(base, -max_id - 1).is_none());
+        assert!(get_max_real_id(bin_step) == max_id);
+    }
+    // log(2^128) / log(1.0001), the bins span every non-zero 128.128 price
+    assert!(get_max_real_id(1) == 887_272);
+    assert!(get_bin_price_x128(-887_272, 1).x128() == U256::from(1));
+    assert!(get_bin_price_x128(887_272, 1) == PriceX128::MAX);
+}
+
+#[test]
+fn test_get_real_id_from_price_x128_rounding() {
+    let price = get_bin_price_x128(-7787, 25).x128();
+    let below = (price - U256::from(1)).to_string();
+    let above = (price + U256::from(1)).to_string();
+
+    assert!(get_real_id_from_price_x128_floor(price.to_string(), 25) == -7787);
+    assert!(get_real_id_from_price_x128_ceil(price.to_string(), 25) == -7787);
+    assert!(get_real_id_from_price_x128_floor(below.clone(), 25) == -7788);
+    assert!(get_real_id_from_price_x128_ceil(below.clone(), 25) == -7787);
+    assert!(get_real_id_from_price_x128_nearest(below, 25) == -7787);
+    assert!(get_real_id_from_price_x128_floor(above.clone(), 25) == -7787);
+    assert!(get_real_id_from_price_x128_ceil(above.clone(), 25) == -7786);
+    assert!(get_real_id_from_price_x128_nearest(above, 25) == -7787);
+
+    // prices below 1 round down, not toward zero
+    let half_bin = (get_bin_price_x128(-1, 100).x128() + constants::scale()) / U256::from(2);
+    assert!(get_real_id_from_price_x128_floor(half_bin.to_string(), 100) == -1);
+    assert!(get_real_id_from_price_x128_ceil(half_bin.to_string(), 100) == 0);
+
+    let max_id = get_max_real_id(100);
+    assert!(get_real_id_from_price_x128_floor(U256::MAX.to_string(), 100) == max_id);
+    assert!(get_real_id_from_price_x128_ceil("1".to_string(), 100) == -max_id);
+}
+
+#[test]
+fn test_get_real_id_from_price_x128_shared_prices() {
+    // near -max_id the prices keep few bits and runs of ids share one
+    let max_id = get_max_real_id(25);
+    let price = get_bin_price_x128(-max_id + 100, 25);
+    let ids: Vec<i32> = (-max_id..=max_id)
+        .take_while(|&id| get_bin_price_x128(id, 25) <= price)
+        .filter(|&id| get_bin_price_x128(id, 25) == price)
+        .collect();
+    assert!(ids.len() > 1);
+    let (first, last) = (ids[0], ids[ids.len() - 1]);
+    assert!(get_real_id_from_price_x128_rounded(price, 25, Rounding::Down) == last);
+    assert!(get_real_id_from_price_x128_rounded(price, 25, Rounding::Up) == first);
+
+    // near max_id the prices saturate to the largest 128.128 number
+    let first = (0..=max_id)
+        .find(|&id| get_bin_price_x128(id, 25) == PriceX128::MAX)
+        .unwrap();
+    assert!(first < max_id);
+    assert!(get_real_id_from_price_x128_rounded(PriceX128::MAX, 25, Rounding::Down) == max_id);
+    assert!(get_real_id_from_price_x128_rounded(PriceX128::MAX, 25, Rounding::Up) == first);
+}
+
+// First and last ids of the run of ids sharing the price of `id`
+#[cfg(test)]
+pub(crate) fn get_ids_sharing_price(id: i32, bin_step: u16) -> (i32, i32) {
+    let max_id = get_max_real_id(bin_step);
+    let price = get_bin_price_x128(id, bin_step);
+    let shares = |other: i32| {
+        (-max_id..=max_id).contains(&other) && get_bin_price_x128(other, bin_step) == price
+    };
+    let (mut first, mut last) = (id, id);
+    while shares(first - 1) {
+        first -= 1;
+    }
+    while shares(last + 1) {
+        last += 1;
+    }
+    (first, last)
+}
+
+#[test]
+fn test_real_id_price_round_trip_exhaustive() {
+    for bin_step in [1u16, 2, 5, 10, 15, 20, 25, 50, 100] {
+        let max_id = get_max_real_id(bin_step);
+        let ids = (-max_id..=max_id).step_by(997).chain(-500..=500);
+        for id in ids.chain([-max_id, max_id]) {
+            let price = get_bin_price_x128(id, bin_step);
+            let (first, last) = get_ids_sharing_price(id, bin_step);
+            assert!(get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Down) == last);
+            assert!(
+                get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Nearest) == last
+            );
+            assert!(get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Up) == first);
+        }
+    }
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn prop_real_id_price_round_trip(bin_step in 1u16..=constants::BASIS_POINT_MAX, seed: i32) {
+        let max_id = get_max_real_id(bin_step);
+        let id = seed % (max_id + 1);
+        let price = get_bin_price_x128(id, bin_step);
+        // the id itself unless its neighbours share its price
+        let (first, last) = get_ids_sharing_price(id, bin_step);
+        proptest::prop_assert_eq!(get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Down), last);
+        proptest::prop_assert_eq!(get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Nearest), last);
+        proptest::prop_assert_eq!(get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Up), first);
+    }
+
+    #[test]
+    fn prop_real_id_brackets_price(bin_step in 1u16..=1000, id in -100_000i32..100_000, offset: u64) {
+        let max_id = get_max_real_id(bin_step);
+        let id = id % max_id;
+        // [lower, upper) spans the ids sharing the price of `id`, only lower for the top run
+        let (first, last) = get_ids_sharing_price(id, bin_step);
+        let lower = get_bin_price_x128(id, bin_step).x128();
+        let span = if last < max_id {
+            get_bin_price_x128(last + 1, bin_step).x128() - lower
+        } else {
+            U256::from(1)
+        };
+        let price = lower + U256::from(offset) % span;
+        let (price_x128, price) = (price, PriceX128::from_x128(price));
+
+        let floor = get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Down);
+        let ceil = get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Up);
+        let nearest = get_real_id_from_price_x128_rounded(price, bin_step, Rounding::Nearest);
+        proptest::prop_assert_eq!(floor, last);
+        proptest::prop_assert_eq!(ceil, if price_x128 == lower { first } else { last + 1 });
+        proptest::prop_assert!(nearest == floor || nearest == ceil);
+    }
+}
+
+#[test]
+fn test_get_bins_in_price_range() {
+    let p = |id: i32| get_bin_price_x128(id, 10).x128();
+    let px = PriceX128::from_x128;
+
+    let bins = get_bins_in_price_range(px(p(-2)), px(p(3)), 10, None);
+    assert!(bins.iter().map(|b| b.real_id).eq(-2..=3));
+    assert!(
+        bins.iter()
+            .all(|b| b.storage_id == get_storage_id_from_real_id(b.real_id))
     );
+    assert!(bins.iter().all(|b| b.price().x128() == p(b.real_id)));
+
+    // only bins whose price lies inside the range
+    let one = U256::from(1);
+    let bins = get_bins_in_price_range(px(p(-2) + one), px(p(3) - one), 10, None);
+    assert!(bins.iter().map(|b| b.real_id).eq(-1..=2));
+    assert!(get_bins_in_price_range(px(p(1) + one), px(p(2) - one), 10, None).is_empty());
+    assert!(get_bins_in_price_range(px(p(2)), px(p(1)), 10, None).is_empty());
+
+    let bins = get_bins_in_price_range(px(p(-2)), px(p(3)), 10, Some(4));
+    assert!(bins.iter().map(|b| b.real_id).eq(-2..=1));
+
+    // clamped to the supported ids
+    let max_id = get_max_real_id(100);
+    let bins = get_bins_in_price_range(px(one), PriceX128::MAX, 100, Some(u32::MAX));
+    assert!(bins.len() == 2 * max_id as usize + 1);
+    assert!(bins[0].storage_id == REAL_ID_SHIFT - max_id as u32);
+    assert!(bins[bins.len() - 1].storage_id == REAL_ID_SHIFT + max_id as u32);
+    // the lowest bins share the smallest prices
+    let bins = get_bins_in_price_range(px(one), px(U256::from(2)), 100, None);
+    assert!(bins.len() > 1 && bins[0].real_id == -max_id);
+    assert!(bins.iter().all(|b| b.price().x128() <= U256::from(2)));
+    // capped by default
+    let bins = get_bins_in_price_range(px(one), PriceX128::MAX, 1, None);
+    assert!(bins.len() == DEFAULT_MAX_BINS as usize && bins[0].real_id == -get_max_real_id(1));
+
+    let bins =
+        get_bins_in_decimal_price_range("0.999".to_string(), "1.0021".to_string(), 9, 9, 10, None);
+    assert!(bins.iter().map(|b| b.real_id).eq(-1..=2));
+    let bins = get_bins_in_price_range_x128(p(0).to_string(), p(0).to_string(), 10, Some(0));
+    assert!(bins.is_empty());
 }
 
 fn i32_neg_from(n: u32) -> i32 {
-    -1 * n as i32
+    -(n as i32)
 }
@@ -0,0 +1,156 @@
+use alloy_primitives::U256;
+
+use crate::{constants, price, units::PriceX128};
+
+// pow only supports |exponent| < 2^20
+const POW_BITS: usize = 20;
+
+// Caches the squarings `u128x128::pow` does for one bin step, and optionally the prices of a
+// window of storage ids (e.g. around the active bin), so that quoting many bins does not redo
+// them. Prices are bit-identical to price::get_price_x128_from_storage_id.
+#[derive(Clone, Debug)]
+pub struct PriceTable {
+    bin_step: u16,
+    // base^(2^i), already inverted when the base is above 1 as pow does
+    squares: [U256; POW_BITS],
+    invert: bool,
+    window_start: u32,
+    window: Vec<PriceX128>,
+}
+
+impl PriceTable {
+    pub fn new(bin_step: u16) -> Self {
+        let base = price::get_base(bin_step);
+        let (mut squared, invert) = if base > max_fraction() {
+            (U256::MAX / base, true)
+        } else {
+            (base, false)
+        };
+
+        let mut squares = [U256::ZERO; POW_BITS];
+        squares[0] = squared;
+        for square in squares.iter_mut().skip(1) {
+            squared = (squared * squared) >> constants::SCALE_OFFSET;
+            *square = squared;
+        }
+
+        PriceTable {
+            bin_step,
+            squares,
+            invert,
+            window_start: 0,
+            window: Vec::new(),
+        }
+    }
+
+    // Also cache the prices of the storage ids in [center - radius, center + radius],
+    // clamped to the supported ids
+    pub fn with_window(bin_step: u16, center_storage_id: u32, radius: u32) -> Self {
+        let mut table = PriceTable::new(bin_step);
+        let max_id = price::get_max_real_id(bin_step);
+        let start = center_storage_id
+            .saturating_sub(radius)
+            .max(price::get_storage_id_from_real_id(-max_id));
+        let end = center_storage_id
+            .saturating_add(radius)
+            .min(price::get_storage_id_from_real_id(max_id));
+        table.window = (start..=end)
+            .map(|storage_id| table.compute(storage_id))
+            .collect();
+        table.window_start = start;
+        table
+    }
+
+    pub fn bin_step(&self) -> u16 {
+        self.bin_step
+    }
+
+    pub fn get_price_x128_from_storage_id(&self, storage_id: u32) -> PriceX128 {
+        let cached = storage_id
+            .checked_sub(self.window_start)
+            .and_then(|offset| self.window.get(offset as usize));
+        match cached {
+            Some(&price) => price,
+            None => self.compute(storage_id),
+        }
+    }
+
+    // Same steps as u128x128::pow with the squarings taken from the table
+    fn compute(&self, storage_id: u32) -> PriceX128 {
+        let exp = price::get_real_id(storage_id);
+        if exp == 0 {
+            return PriceX128::ONE;
+        };
+
+        let abs_y = exp.unsigned_abs();
+        let mut result = U256::ZERO;
+        if abs_y < 1 << POW_BITS {
+            result = PriceX128::ONE.x128();
+            for (bit, squared) in self.squares.iter().enumerate() {
+                if abs_y & (1 << bit) != 0 {
+                    result = (result * squared) >> constants::SCALE_OFFSET;
+                };
+            }
+        };
+
+        // revert if y is too big or if x^y underflowed
+        if result == U256::ZERO {
+            panic!("ErrPowUnderflow")
+        };
+
+        if self.invert != exp.is_negative() {
+            PriceX128::from_x128(U256::MAX / result)
+        } else {
+            PriceX128::from_x128(result)
+        }
+    }
+}
+
+fn max_fraction() -> U256 {
+    constants::SCALE - U256::from(1)
+}
+
+#[test]
+fn test_matches_pow() {
+    for bin_step in [1u16, 2, 5, 10, 25, 100, constants::BASIS_POINT_MAX] {
+        let max_id = price::get_max_real_id(bin_step);
+        let table = PriceTable::new(bin_step);
+        let ids = (-max_id..=max_id)
+            .step_by(4999)
+            .chain(-max_id.min(300)..=max_id.min(300));
+        for real_id in ids.chain([-max_id, max_id]) {
+            let storage_id = price::get_storage_id_from_real_id(real_id);
+            assert!(
+                table.get_price_x128_from_storage_id(storage_id)
+                    == price::get_price_x128_from_storage_id(storage_id, bin_step)
+            );
+        }
+    }
+}
+
+#[test]
+fn test_window() {
+    let center = price::get_storage_id_from_real_id(-7787);
+    let table = PriceTable::with_window(25, center, 50);
+    assert!(table.window.len() == 101);
+    for storage_id in center - 60..=center + 60 {
+        assert!(
+            table.get_price_x128_from_storage_id(storage_id)
+                == price::get_price_x128_from_storage_id(storage_id, 25)
+        );
+    }
+    assert!(PriceTable::with_window(25, 3, 10).window.is_empty());
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn prop_matches_pow(bin_step in 1u16..=constants::BASIS_POINT_MAX, seed: i32) {
+        let max_id = price::get_max_real_id(bin_step);
+        let storage_id = price::get_storage_id_from_real_id(seed % (max_id + 1));
+        proptest::prop_assert_eq!(
+            PriceTable::new(bin_step).get_price_x128_from_storage_id(storage_id),
+            price::get_price_x128_from_storage_id(storage_id, bin_step)
+        );
+    }
+}
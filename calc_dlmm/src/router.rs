@@ -0,0 +1,476 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use serde::Serialize;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    clmm::pool::ClmmPool,
+    pool::{Pool, PoolQuote},
+    swap_result::DlmmPair,
+    units::u64_string,
+};
+
+// Route search over local pool snapshots, DLMM and CLMM alike. Routes are the simple paths
+// of up to max_hops pools from the input token to the output one, grown one hop at a time
+// with the whole amount: only the MAX_PATHS partial routes paying the most for each token
+// reached are extended further, and the MAX_PATHS best complete routes are kept. A route
+// not filling the whole amount is dropped. A split spreads the amount in over up to
+// max_splits routes, chunk by chunk to the route paying the most for it on the pools as the
+// previous chunks left them
+
+// Chunks of the amount in a split is built from
+const SPLIT_PARTS: u64 = 20;
+
+// Longest route searched, as the aggregator
+pub const MAX_HOPS: u32 = 3;
+
+// Routes kept per token reached at each hop, those paying the most for the whole amount
+const MAX_PATHS: usize = 16;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Default)]
+pub struct PoolGraph {
+    pools: Vec<Box<dyn Pool>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RouteHop {
+    // index of the pool in the graph
+    pub pool_index: usize,
+    pub coin_in: String,
+    pub coin_out: String,
+    pub a2b: bool,
+    #[serde(with = "u64_string")]
+    pub amount_in: u64,
+    #[serde(with = "u64_string")]
+    pub amount_out: u64,
+    #[serde(with = "u64_string")]
+    pub fee: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Route {
+    pub hops: Vec<RouteHop>,
+    #[serde(with = "u64_string")]
+    pub amount_in: u64,
+    #[serde(with = "u64_string")]
+    pub amount_out: u64,
+}
+
+// No routes when the output token can't be reached or no route fills the amount in
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RouteResult {
+    pub routes: Vec<Route>,
+    #[serde(with = "u64_string")]
+    pub amount_in: u64,
+    #[serde(with = "u64_string")]
+    pub amount_out: u64,
+}
+
+// Pool index and direction of each hop
+type Path = Vec<(usize, bool)>;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PoolGraph {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Self {
+        PoolGraph::default()
+    }
+
+    // The pairs and pools need their coin types. Return the pool index
+    pub fn add_dlmm_pair(&mut self, pair: &DlmmPair) -> usize {
+        self.add_pool(Box::new(pair.clone()))
+    }
+
+    pub fn add_clmm_pool(&mut self, pool: &ClmmPool) -> usize {
+        self.add_pool(Box::new(pool.clone()))
+    }
+
+    // Return a RouteResult as JSON
+    #[cfg(feature = "wasm")]
+    pub fn find_route_json(
+        &self,
+        coin_in: &str,
+        coin_out: &str,
+        amount_in: u64,
+        max_hops: u32,
+        max_splits: u32,
+        timestamp_ms: u64,
+    ) -> Result<String, JsError> {
+        let result = self.find_route(
+            coin_in,
+            coin_out,
+            amount_in,
+            max_hops,
+            max_splits,
+            timestamp_ms,
+        );
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+impl PoolGraph {
+    pub fn add_pool(&mut self, pool: Box<dyn Pool>) -> usize {
+        let (coin_type_a, coin_type_b) = pool.coin_types();
+        assert!(
+            !coin_type_a.is_empty() && !coin_type_b.is_empty(),
+            "ErrUnknownCoinTypes"
+        );
+        self.pools.push(pool);
+        self.pools.len() - 1
+    }
+
+    pub fn pools(&self) -> &[Box<dyn Pool>] {
+        &self.pools
+    }
+
+    pub fn find_route(
+        &self,
+        coin_in: &str,
+        coin_out: &str,
+        amount_in: u64,
+        max_hops: u32,
+        max_splits: u32,
+        timestamp_ms: u64,
+    ) -> RouteResult {
+        assert!(
+            (1..=MAX_HOPS).contains(&max_hops) && max_splits > 0,
+            "ErrInvalidRouteParams"
+        );
+        let routes = self.best_routes(coin_in, coin_out, amount_in, max_hops, timestamp_ms);
+        let best_single = routes.first().cloned();
+        let paths: Vec<Path> = routes
+            .iter()
+            .map(|route| {
+                route
+                    .hops
+                    .iter()
+                    .map(|hop| (hop.pool_index, hop.a2b))
+                    .collect()
+            })
+            .collect();
+
+        let result = match best_single {
+            Some(route) => RouteResult {
+                amount_in: route.amount_in,
+                amount_out: route.amount_out,
+                routes: vec![route],
+            },
+            None => RouteResult::default(),
+        };
+
+        let split = (max_splits > 1 && paths.len() > 1)
+            .then(|| self.split(&paths, amount_in, max_splits as usize, timestamp_ms))
+            .flatten();
+        match split {
+            Some(split) if split.amount_out > result.amount_out => split,
+            _ => result,
+        }
+    }
+
+    // Best simple routes from coin_in to coin_out, a token is never visited twice
+    fn best_routes(
+        &self,
+        coin_in: &str,
+        coin_out: &str,
+        amount_in: u64,
+        max_hops: u32,
+        timestamp_ms: u64,
+    ) -> Vec<Route> {
+        let mut routes = Vec::new();
+        let mut frontier = vec![Route {
+            hops: Vec::new(),
+            amount_in,
+            amount_out: amount_in,
+        }];
+        for length in 1..=max_hops {
+            let mut next = Vec::new();
+            for route in &frontier {
+                let coin = route
+                    .hops
+                    .last()
+                    .map_or(coin_in, |hop| hop.coin_out.as_str());
+                for (index, pool) in self.pools.iter().enumerate() {
+                    let (coin_type_a, coin_type_b) = pool.coin_types();
+                    let (a2b, next_coin) = if coin_type_a == coin {
+                        (true, coin_type_b)
+                    } else if coin_type_b == coin {
+                        (false, coin_type_a)
+                    } else {
+                        continue;
+                    };
+                    let visited = next_coin == coin_in
+                        || route.hops.iter().any(|hop| hop.coin_out == next_coin);
+                    if visited || (next_coin != coin_out && length == max_hops) {
+                        continue;
+                    }
+
+                    let quote = pool.quote_exact_in(route.amount_out, a2b, timestamp_ms);
+                    let mut extended = route.clone();
+                    let Some(amount_out) = self.push_hop(&mut extended.hops, index, a2b, quote)
+                    else {
+                        continue;
+                    };
+                    extended.amount_out = amount_out;
+                    if next_coin == coin_out {
+                        routes.push(extended);
+                    } else {
+                        next.push(extended);
+                    }
+                }
+            }
+            routes = keep_best(routes);
+            frontier = keep_best(next);
+        }
+        routes
+    }
+
+    // None when a hop can't fill its amount in or gives nothing out
+    fn quote_route(
+        &self,
+        pools: &[Box<dyn Pool>],
+        path: &Path,
+        amount_in: u64,
+        timestamp_ms: u64,
+    ) -> Option<Route> {
+        let mut amount = amount_in;
+        let mut hops = Vec::with_capacity(path.len());
+        for &(index, a2b) in path {
+            let quote = pools[index].quote_exact_in(amount, a2b, timestamp_ms);
+            amount = self.push_hop(&mut hops, index, a2b, quote)?;
+        }
+        Some(Route {
+            hops,
+            amount_in,
+            amount_out: amount,
+        })
+    }
+
+    // Same as quote_route, applying the swaps to the pools
+    fn swap_route(
+        &self,
+        pools: &mut [Box<dyn Pool>],
+        path: &Path,
+        amount_in: u64,
+        timestamp_ms: u64,
+    ) -> Option<Route> {
+        let mut amount = amount_in;
+        let mut hops = Vec::with_capacity(path.len());
+        for &(index, a2b) in path {
+            let quote = pools[index].swap_exact_in(amount, a2b, timestamp_ms);
+            amount = self.push_hop(&mut hops, index, a2b, quote)?;
+        }
+        Some(Route {
+            hops,
+            amount_in,
+            amount_out: amount,
+        })
+    }
+
+    fn push_hop(
+        &self,
+        hops: &mut Vec<RouteHop>,
+        index: usize,
+        a2b: bool,
+        quote: PoolQuote,
+    ) -> Option<u64> {
+        if quote.amount_left > 0 || quote.amount_out == 0 {
+            return None;
+        }
+        let (coin_type_a, coin_type_b) = self.pools[index].coin_types();
+        let (coin_in, coin_out) = if a2b {
+            (coin_type_a, coin_type_b)
+        } else {
+            (coin_type_b, coin_type_a)
+        };
+        hops.push(RouteHop {
+            pool_index: index,
+            coin_in: coin_in.to_string(),
+            coin_out: coin_out.to_string(),
+            a2b,
+            amount_in: quote.amount_in,
+            amount_out: quote.amount_out,
+            fee: quote.fee,
+        });
+        Some(quote.amount_out)
+    }
+
+    // Greedy split, then the routes replayed one after the other as a transaction would
+    fn split(
+        &self,
+        paths: &[Path],
+        amount_in: u64,
+        max_splits: usize,
+        timestamp_ms: u64,
+    ) -> Option<RouteResult> {
+        let chunk = amount_in / SPLIT_PARTS;
+        if chunk == 0 {
+            return None;
+        }
+        let mut pools = self.pools.clone();
+        let mut allocations = vec![0u64; paths.len()];
+        let mut used: Vec<usize> = Vec::new();
+        for part in 0..SPLIT_PARTS {
+            let amount = if part == SPLIT_PARTS - 1 {
+                amount_in - chunk * (SPLIT_PARTS - 1)
+            } else {
+                chunk
+            };
+            let candidates: Vec<usize> = if used.len() < max_splits {
+                (0..paths.len()).collect()
+            } else {
+                used.clone()
+            };
+            let (best, _) = candidates
+                .into_iter()
+                .filter_map(|i| {
+                    self.quote_route(&pools, &paths[i], amount, timestamp_ms)
+                        .map(|route| (i, route.amount_out))
+                })
+                .max_by_key(|&(_, amount_out)| amount_out)?;
+            self.swap_route(&mut pools, &paths[best], amount, timestamp_ms)?;
+            allocations[best] += amount;
+            if !used.contains(&best) {
+                used.push(best);
+            }
+        }
+
+        let mut pools = self.pools.clone();
+        let mut result = RouteResult {
+            amount_in,
+            ..Default::default()
+        };
+        for i in used {
+            let route = self.swap_route(&mut pools, &paths[i], allocations[i], timestamp_ms)?;
+            result.amount_out += route.amount_out;
+            result.routes.push(route);
+        }
+        Some(result)
+    }
+}
+
+// The MAX_PATHS routes paying the most per token they end at, in that order. Ties keep the
+// pool order
+fn keep_best(mut routes: Vec<Route>) -> Vec<Route> {
+    routes.sort_by_key(|route| Reverse(route.amount_out));
+    let mut kept: HashMap<String, usize> = HashMap::new();
+    routes.retain(|route| {
+        let coin = &route.hops.last().unwrap().coin_out;
+        let count = kept.entry(coin.clone()).or_default();
+        *count += 1;
+        *count <= MAX_PATHS
+    });
+    routes
+}
+
+#[cfg(test)]
+const SUI: &str = "0x2::sui::SUI";
+#[cfg(test)]
+const USDC: &str = "0x5::usdc::USDC";
+#[cfg(test)]
+const USDT: &str = "0x6::usdt::USDT";
+
+#[test]
+fn test_route_direct_and_split() {
+    // two shallow full range pools at the price 1 and the deeper pair
+    let mut graph = PoolGraph::new();
+    for _ in 0..2 {
+        let mut pool = ClmmPool::new(1 << 64, 1_000_000_000, 2500, 0, Vec::new());
+        pool.set_coin_types(SUI.to_string(), USDC.to_string());
+        graph.add_clmm_pool(&pool);
+    }
+    let mut pair = crate::swap_result::test_pair(25, 50);
+    pair.set_coin_types(SUI.to_string(), USDC.to_string());
+    graph.add_dlmm_pair(&pair);
+
+    let single = graph.find_route(SUI, USDC, 100_000_000, 2, 1, 0);
+    assert!(single.routes.len() == 1 && single.amount_in == 100_000_000);
+    let route = &single.routes[0];
+    assert!(route.hops.len() == 1 && route.hops[0].coin_in == SUI);
+    assert!(route.amount_out == route.hops[0].amount_out);
+
+    // the shallow pools share the amount
+    let split = graph.find_route(SUI, USDC, 100_000_000, 2, 3, 0);
+    assert!(split.amount_out > single.amount_out);
+    assert!(split.routes.len() >= 2 && split.routes.len() <= 3);
+    let routes_in: u64 = split.routes.iter().map(|route| route.amount_in).sum();
+    let routes_out: u64 = split.routes.iter().map(|route| route.amount_out).sum();
+    assert!(routes_in == split.amount_in && routes_out == split.amount_out);
+    let pools: Vec<usize> = split
+        .routes
+        .iter()
+        .map(|route| route.hops[0].pool_index)
+        .collect();
+    assert!(pools.contains(&0) && pools.contains(&1));
+
+    // USDC to SUI walks the pools the other way
+    let back = graph.find_route(USDC, SUI, 1_000_000, 1, 1, 0);
+    assert!(!back.routes[0].hops[0].a2b && back.routes[0].hops[0].coin_out == SUI);
+}
+
+#[test]
+fn test_route_multi_hop() {
+    let mut graph = PoolGraph::new();
+    for (coin_type_a, coin_type_b) in [(SUI, USDC), (USDT, USDC)] {
+        let mut pool = crate::clmm::pool::test_pool();
+        pool.set_coin_types(coin_type_a.to_string(), coin_type_b.to_string());
+        graph.add_clmm_pool(&pool);
+    }
+
+    assert!(
+        graph
+            .find_route(SUI, USDT, 1_000_000, 1, 1, 0)
+            .routes
+            .is_empty()
+    );
+    let result = graph.find_route(SUI, USDT, 1_000_000, 2, 1, 0);
+    let hops = &result.routes[0].hops;
+    assert!(hops.len() == 2 && hops[0].coin_out == USDC && hops[1].coin_in == USDC);
+    assert!(hops[0].a2b && !hops[1].a2b && hops[1].pool_index == 1);
+    assert!(hops[1].amount_in == hops[0].amount_out);
+    // two 0.25% fees at the price 1
+    assert!(result.amount_out < 995_007 && result.amount_out > 994_000);
+    // the graph is unchanged by the search
+    assert!(graph.find_route(SUI, USDT, 1_000_000, 2, 1, 0) == result);
+}
+
+#[test]
+fn test_route_keeps_the_best_paths() {
+    let mut graph = PoolGraph::new();
+    // full range pools at the price 1, deeper and deeper
+    for liquidity in 1..=(MAX_PATHS as u128 + 4) {
+        let mut pool = ClmmPool::new(1 << 64, liquidity * 1_000_000_000, 2500, 0, Vec::new());
+        pool.set_coin_types(SUI.to_string(), USDC.to_string());
+        graph.add_clmm_pool(&pool);
+    }
+    // the deepest pools are kept and the split spreads over them
+    let single = graph.find_route(SUI, USDC, 100_000_000, 1, 1, 0);
+    assert!(single.routes[0].hops[0].pool_index == MAX_PATHS + 3);
+    let split = graph.find_route(SUI, USDC, 100_000_000, 1, 3, 0);
+    assert!(split.amount_out > single.amount_out);
+    assert!(
+        split
+            .routes
+            .iter()
+            .all(|route| route.hops[0].pool_index >= 4)
+    );
+
+    // only the best partial routes through USDC are extended to USDT
+    for liquidity in 1..=(MAX_PATHS as u128 + 4) {
+        let mut pool = ClmmPool::new(1 << 64, liquidity * 1_000_000_000, 2500, 0, Vec::new());
+        pool.set_coin_types(USDC.to_string(), USDT.to_string());
+        graph.add_clmm_pool(&pool);
+    }
+    let result = graph.find_route(SUI, USDT, 100_000_000, 2, 1, 0);
+    let hops = &result.routes[0].hops;
+    assert!(hops.len() == 2 && hops[0].pool_index == MAX_PATHS + 3);
+    assert!(hops[1].pool_index == 2 * MAX_PATHS + 7);
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidRouteParams")]
+fn test_route_too_many_hops() {
+    PoolGraph::new().find_route(SUI, USDC, 1_000_000, MAX_HOPS + 1, 1, 0);
+}
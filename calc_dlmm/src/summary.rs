@@ -0,0 +1,204 @@
+use alloy_primitives::{U256, U512, Uint};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{constants, price, swap_result::DlmmPair, units::PriceX128};
+
+type U1024 = Uint<1024, 16>;
+
+// Aggregates of a pair snapshot, what the pair_liquidity devInspect call returns and more
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PairSummary {
+    pub total_x: u128,
+    pub total_y: u128,
+    // average of the bin prices weighted by the bin liquidity, the active bin price when
+    // the pair is empty
+    average_price_x128: PriceX128,
+    pub active_reserve_x: u64,
+    pub active_reserve_y: u64,
+    // share of the active bin value held in X, 1e9 precision
+    pub active_x_share: u64,
+    // non-empty bins, None when the pair is empty
+    pub lower_real_id: Option<i32>,
+    pub upper_real_id: Option<i32>,
+    // sum of the bin liquidities, 128.128, saturating
+    liquidity: U256,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PairSummary {
+    // 2^128 price as an int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn average_price_x128(&self) -> String {
+        self.average_price_x128.to_string()
+    }
+
+    // 128.128 liquidity as an int string
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn liquidity_x128(&self) -> String {
+        self.liquidity.to_string()
+    }
+}
+
+impl PairSummary {
+    pub fn average_price(&self) -> PriceX128 {
+        self.average_price_x128
+    }
+
+    pub fn liquidity(&self) -> U256 {
+        self.liquidity
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    pub fn get_summary(&self) -> PairSummary {
+        let (mut total_x, mut total_y) = (0u128, 0u128);
+        let mut liquidity = U256::ZERO;
+        // bin liquidities are < 2^321 and prices < 2^256, over at most 2^24 bins
+        let mut liquidity_512 = U512::ZERO;
+        let mut weighted_price = U1024::ZERO;
+        let (mut lower_real_id, mut upper_real_id) = (None::<i32>, None::<i32>);
+        // price_q128 is zero in some snapshots, the bins are priced from their id
+        let bin_price =
+            |storage_id: u32| price::get_price_x128_from_storage_id(storage_id, self.bin_step());
+        for bin in self.bins() {
+            if bin.reserve_x == 0 && bin.reserve_y == 0 {
+                continue;
+            };
+            total_x += bin.reserve_x as u128;
+            total_y += bin.reserve_y as u128;
+
+            let price = bin_price(bin.storage_id);
+            let bin_liquidity = get_liquidity_512(bin.reserve_x, bin.reserve_y, price);
+            liquidity = liquidity.saturating_add(bin_liquidity.saturating_to::<U256>());
+            liquidity_512 += bin_liquidity;
+            weighted_price += U1024::from(price.x128()) * U1024::from(bin_liquidity);
+
+            let real_id = price::get_real_id(bin.storage_id);
+            lower_real_id = Some(lower_real_id.map_or(real_id, |id| id.min(real_id)));
+            upper_real_id = Some(upper_real_id.map_or(real_id, |id| id.max(real_id)));
+        }
+
+        let active_price = bin_price(self.active_id());
+        let average_price_x128 = if liquidity_512 == U512::ZERO {
+            active_price
+        } else {
+            // an average of the prices, < 2^256
+            PriceX128::from_x128((weighted_price / U1024::from(liquidity_512)).to::<U256>())
+        };
+
+        let (active_reserve_x, active_reserve_y, active_x_share) = match self.bin(self.active_id())
+        {
+            Some(active) if active.reserve_x > 0 || active.reserve_y > 0 => {
+                let liquidity_x = get_liquidity_512(active.reserve_x, 0, active_price);
+                let active_liquidity =
+                    get_liquidity_512(active.reserve_x, active.reserve_y, active_price);
+                let share = if active_liquidity == U512::ZERO {
+                    0
+                } else {
+                    (liquidity_x * U512::from(constants::PRECISION) / active_liquidity).to::<u64>()
+                };
+                (active.reserve_x, active.reserve_y, share)
+            }
+            _ => (0, 0, 0),
+        };
+
+        PairSummary {
+            total_x,
+            total_y,
+            average_price_x128,
+            active_reserve_x,
+            active_reserve_y,
+            active_x_share,
+            lower_real_id,
+            upper_real_id,
+            liquidity,
+        }
+    }
+}
+
+// Same as bin::get_liquidity without its overflow check, < 2^321
+fn get_liquidity_512(amount_x: u64, amount_y: u64, price_q128: PriceX128) -> U512 {
+    U512::from(price_q128.x128()) * U512::from(amount_x)
+        + (U512::from(amount_y) << constants::SCALE_OFFSET)
+}
+
+#[test]
+fn test_summary() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 10);
+    let summary = pair.get_summary();
+    assert!(summary.total_x == 11_000_000 && summary.total_y == 11_000_000);
+    assert!(summary.lower_real_id == Some(-10) && summary.upper_real_id == Some(10));
+    assert!((summary.active_reserve_x, summary.active_reserve_y) == (1_000_000, 1_000_000));
+    // price 1 in the active bin
+    assert!(summary.active_x_share == 500_000_000);
+
+    let liquidity: U256 = pair
+        .bins()
+        .map(|bin| {
+            crate::swap_result::bin::get_liquidity(bin.reserve_x, bin.reserve_y, bin.price_q128)
+        })
+        .sum();
+    assert!(summary.liquidity() == liquidity);
+    assert!(summary.liquidity_x128() == liquidity.to_string());
+    // the X bins above 1 are worth more than the Y bins below
+    let lowest = price::get_bin_price_x128(-10, 25);
+    let highest = price::get_bin_price_x128(10, 25);
+    assert!(summary.average_price() > PriceX128::ONE);
+    assert!(summary.average_price() > lowest && summary.average_price() < highest);
+}
+
+#[test]
+fn test_summary_empty_pair() {
+    let mut pair = crate::swap_result::test_one_sided_pair(25, 2);
+    for bin in pair.bins.values_mut() {
+        bin.reserve_x = 0;
+        bin.reserve_y = 0;
+    }
+    let summary = pair.get_summary();
+    assert!(summary.total_x == 0 && summary.liquidity() == U256::ZERO);
+    assert!(summary.lower_real_id.is_none() && summary.upper_real_id.is_none());
+    assert!(summary.average_price() == PriceX128::ONE && summary.active_x_share == 0);
+}
+
+#[test]
+fn test_summary_liquidity_saturates() {
+    // X only in the bins at the highest prices
+    let top = price::get_storage_id_from_real_id(price::get_max_real_id(25));
+    let params = crate::swap_result::DlmmPairParameter {
+        active_index: top,
+        index_reference: top,
+        ..Default::default()
+    };
+    let bins = (top - 2..=top)
+        .map(|storage_id| crate::swap_result::Bin {
+            storage_id,
+            reserve_x: u64::MAX,
+            ..Default::default()
+        })
+        .collect();
+    let summary = DlmmPair::new(25, params, bins).get_summary();
+    assert!(summary.liquidity() == U256::MAX && summary.active_x_share == 1_000_000_000);
+    let lowest = price::get_price_x128_from_storage_id(top - 2, 25);
+    assert!(summary.average_price() >= lowest);
+}
+
+#[test]
+fn test_summary_without_bin_prices() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 10);
+    let mut unpriced = pair.clone();
+    for bin in unpriced.bins.values_mut() {
+        bin.price_q128 = PriceX128::ZERO;
+    }
+    // X only in the active bin
+    let active_id = pair.active_id();
+    unpriced.bins.get_mut(&active_id).unwrap().reserve_y = 0;
+    let summary = unpriced.get_summary();
+    assert!(summary.active_x_share == 1_000_000_000);
+    assert!(summary.average_price() > PriceX128::ONE);
+    unpriced.bins.get_mut(&active_id).unwrap().reserve_y = 1_000_000;
+    assert!(unpriced.get_summary() == pair.get_summary());
+}
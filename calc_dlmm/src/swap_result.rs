@@ -1,38 +1,71 @@
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use alloy_primitives::U256;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::{constants, price, uint_safe};
-
-#[wasm_bindgen]
+use crate::{
+    bcs::{self, BcsError},
+    constants,
+    events::SwapBin,
+    price,
+    price_table::PriceTable,
+    uint_safe,
+    units::{Fee1e9, PriceX128, u32_number, u64_string, u256_string, u256_string_vec},
+};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DlmmPair {
-    params: DlmmPairParameter,
-    bins: HashMap<u32, Bin>,
-    bin_step: u16,
+    pub(crate) params: DlmmPairParameter,
+    // a list ordered by storage id when serialized
+    #[serde(with = "bins_by_id")]
+    pub(crate) bins: HashMap<u32, Bin>,
+    pub(crate) bin_step: u16,
+    // X and Y
+    #[serde(default)]
+    pub(crate) coin_type_a: String,
+    #[serde(default)]
+    pub(crate) coin_type_b: String,
+    #[serde(skip)]
+    price_table: Option<PriceTable>,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SwapOutResult {
     pub amount_in_left: u64,
     pub amount_out: u64,
     pub fee: u64,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SwapInResult {
     pub amount_in: u64,
     pub amount_out_left: u64,
     pub fee: u64,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn get_swap_out(
     pair: &DlmmPair,
     amount_in: u64,
     swap_for_y: bool,
     timestamp_ms: u64,
 ) -> SwapOutResult {
+    get_swap_out_bins(pair, amount_in, swap_for_y, timestamp_ms).0
+}
+
+// Same as get_swap_out, also returning what the swap does in each bin it crosses
+pub(crate) fn get_swap_out_bins(
+    pair: &DlmmPair,
+    amount_in: u64,
+    swap_for_y: bool,
+    timestamp_ms: u64,
+) -> (SwapOutResult, Vec<SwapBin>) {
+    let mut steps = Vec::new();
     let mut amount_in_left = amount_in;
     let mut params = pair.params.clone();
     let mut id = params.active_index;
@@ -65,21 +98,28 @@ pub fn get_swap_out(
             ) = bin::get_amounts(
                 bin.reserve_x,
                 bin.reserve_y,
-                pair.bin_step,
+                pair.get_price_x128(id),
                 total_fee,
                 swap_for_y,
-                id,
                 amount_in_left,
             );
 
-            if amounts_in_with_fees_x > 0 && swap_for_y {
-                amount_in_left = amount_in_left - amounts_in_with_fees_x;
-                amount_out = amount_out + amounts_out_of_bin_y;
-                fee = fee + total_fees_x;
-            } else if amounts_in_with_fees_y > 0 && !swap_for_y {
-                amount_in_left = amount_in_left - amounts_in_with_fees_y;
-                amount_out = amount_out + amounts_out_of_bin_x;
-                fee = fee + total_fees_y;
+            let (bin_amount_in, bin_amount_out, bin_fee) = if swap_for_y {
+                (amounts_in_with_fees_x, amounts_out_of_bin_y, total_fees_x)
+            } else {
+                (amounts_in_with_fees_y, amounts_out_of_bin_x, total_fees_y)
+            };
+            if bin_amount_in > 0 {
+                amount_in_left -= bin_amount_in;
+                amount_out += bin_amount_out;
+                fee += bin_fee;
+                steps.push(SwapBin {
+                    storage_id: id,
+                    amount_in: bin_amount_in,
+                    amount_out: bin_amount_out,
+                    fee: bin_fee,
+                    protocol_fee: fee::get_protocol_fee_amount(bin_fee, params.protocol_share),
+                });
             };
         };
 
@@ -94,14 +134,15 @@ pub fn get_swap_out(
         };
     }
 
-    SwapOutResult {
+    let result = SwapOutResult {
         amount_in_left,
         amount_out,
         fee,
-    }
+    };
+    (result, steps)
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn get_swap_in(
     pair: &DlmmPair,
     amount_out: u64,
@@ -127,7 +168,7 @@ pub fn get_swap_in(
         };
 
         if bin_reserve > 0 {
-            let price_q128 = price::get_price_x128_from_storage_id(id, pair.bin_step);
+            let price_q128 = pair.get_price_x128(id);
             let amount_out_of_bin = if bin_reserve > amount_out_left {
                 amount_out_left
             } else {
@@ -137,18 +178,18 @@ pub fn get_swap_in(
             params.update_volatility_accumulator(id);
 
             let amount_in_without_fee = if swap_for_y {
-                (U256::from(amount_out_of_bin) << U256::from(constants::SCALE_OFFSET)) / price_q128
+                price_q128.y_to_x(amount_out_of_bin)
             } else {
-                (U256::from(amount_out_of_bin) * price_q128) >> U256::from(constants::SCALE_OFFSET)
+                price_q128.x_to_y(amount_out_of_bin)
             };
             let amount_in_without_fee = uint_safe::safe64(amount_in_without_fee);
 
             let total_fee = params.get_total_fee(pair.bin_step);
             let fee_amount = fee::get_fee_amount_from(amount_in_without_fee, total_fee);
 
-            amount_in = amount_in + amount_in_without_fee + fee_amount;
-            amount_out_left = amount_out_left - amount_out_of_bin;
-            fee = fee + fee_amount;
+            amount_in += amount_in_without_fee + fee_amount;
+            amount_out_left -= amount_out_of_bin;
+            fee += fee_amount;
         };
 
         if amount_out_left == 0 {
@@ -169,12 +210,116 @@ pub fn get_swap_in(
     }
 }
 
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    // Cache bin prices for the bins within `radius` of the active bin, the quotes are unchanged
+    pub fn enable_price_table(&mut self, radius: u32) {
+        self.price_table = Some(PriceTable::with_window(
+            self.bin_step,
+            self.params.active_index,
+            radius,
+        ));
+    }
+
+    pub fn disable_price_table(&mut self) {
+        self.price_table = None;
+    }
+
+    // Pair from the BCS bytes of its PairParameter and of a vector<Bin>, as returned by
+    // devInspectTransactionBlock
+    pub fn from_bcs(bin_step: u16, params: &[u8], bins: &[u8]) -> Result<DlmmPair, BcsError> {
+        Ok(DlmmPair::new(
+            bin_step,
+            bcs::decode_pair_parameter(params)?,
+            bcs::decode_bins(bins)?,
+        ))
+    }
+
+    // Add or replace the bins of another page of vector<Bin> BCS bytes
+    pub fn insert_bins_bcs(&mut self, bins: &[u8]) -> Result<(), BcsError> {
+        for bin in bcs::decode_bins(bins)? {
+            self.bins.insert(bin.storage_id, bin);
+        }
+        Ok(())
+    }
+
+    pub fn set_coin_types(&mut self, coin_type_a: String, coin_type_b: String) {
+        self.coin_type_a = coin_type_a;
+        self.coin_type_b = coin_type_b;
+    }
+
+    // Pair snapshot in the JSON layout of the SDK events:
+    // { params: EventPairParams, bins: EventBin[], bin_step, coin_type_a?, coin_type_b? }
+    #[cfg(feature = "wasm")]
+    pub fn from_json(json: &str) -> Result<DlmmPair, JsError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
 impl DlmmPair {
+    pub fn new(bin_step: u16, params: DlmmPairParameter, bins: Vec<Bin>) -> Self {
+        DlmmPair {
+            params,
+            bins: bins.into_iter().map(|bin| (bin.storage_id, bin)).collect(),
+            bin_step,
+            coin_type_a: String::new(),
+            coin_type_b: String::new(),
+            price_table: None,
+        }
+    }
+
+    pub fn coin_types(&self) -> (&str, &str) {
+        (&self.coin_type_a, &self.coin_type_b)
+    }
+
+    pub fn bin_step(&self) -> u16 {
+        self.bin_step
+    }
+
+    pub fn params(&self) -> &DlmmPairParameter {
+        &self.params
+    }
+
+    pub fn active_id(&self) -> u32 {
+        self.params.active_index
+    }
+
+    pub fn bin(&self, storage_id: u32) -> Option<&Bin> {
+        self.bins.get(&storage_id)
+    }
+
+    // In no particular order
+    pub fn bins(&self) -> impl Iterator<Item = &Bin> {
+        self.bins.values()
+    }
+
+    // Compact binary snapshot of the pair, without the price table
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+
+    fn get_price_x128(&self, storage_id: u32) -> PriceX128 {
+        match &self.price_table {
+            Some(table) => table.get_price_x128_from_storage_id(storage_id),
+            None => price::get_price_x128_from_storage_id(storage_id, self.bin_step),
+        }
+    }
+
+    // Selling X walks down to the bins holding Y, selling Y walks up to the bins holding X
     fn get_next_non_empty_bin_internal(&self, swap_for_y: bool, id: u32) -> (u32, bool) {
         if swap_for_y {
-            self.find_first_left(id)
-        } else {
             self.find_first_right(id)
+        } else {
+            self.find_first_left(id)
         }
     }
 
@@ -199,58 +344,89 @@ impl DlmmPair {
     }
 }
 
+// Mirrors the on-chain bin, serialized with the field names of the SDK's EventBin
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Bin {
-    storage_id: u32,
-    price_q128: U256,
-    reserve_x: u64,
-    reserve_y: u64,
-
-    fee_growth_x: U256,
-    fee_growth_y: U256,
-    rewarder_growth: Vec<U256>,
-    distribution_growth: U256,
+    #[serde(with = "u32_number")]
+    pub storage_id: u32,
+    pub price_q128: PriceX128,
+    #[serde(with = "u64_string")]
+    pub reserve_x: u64,
+    #[serde(with = "u64_string")]
+    pub reserve_y: u64,
+
+    #[serde(with = "u256_string")]
+    pub staked_liquidity: U256,
+    #[serde(with = "u64_string")]
+    pub staked_lp_amount: u64,
+
+    #[serde(with = "u64_string")]
+    pub fee_x: u64,
+    #[serde(with = "u64_string")]
+    pub fee_y: u64,
+    #[serde(with = "u256_string")]
+    pub fee_growth_x: U256,
+    #[serde(with = "u256_string")]
+    pub fee_growth_y: U256,
+    #[serde(with = "u256_string_vec")]
+    pub rewarder_growth: Vec<U256>,
+    #[serde(with = "u256_string")]
+    pub distribution_growth: U256,
+    #[serde(with = "u64_string")]
+    pub distribution_last_updated: u64,
 }
 
-#[derive(Clone, Debug)]
+// Serialized with the field names of the SDK's EventPairParams
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DlmmPairParameter {
-    base_factor: u16,                // 16bit, basis_point
-    filter_period: u16,              // 12bit
-    decay_period: u16,               // 12bit
-    reduction_factor: u16,           // 14bit
-    variable_fee_control: u32,       // 24bit, basis_point
-    protocol_share: u16,             // 14bit
-    max_volatility_accumulator: u32, // 20bit, basis_point
-    volatility_accumulator: u32,     // 20bit, basis_point
-    volatility_reference: u32,       // 20bit
-    index_reference: u32,            // 24bit
-    time_of_last_update: u64,
-    oracle_index: u16,
-    active_index: u32, // 24bit
+    pub base_factor: u16,                // 16bit, basis_point
+    pub filter_period: u16,              // 12bit
+    pub decay_period: u16,               // 12bit
+    pub reduction_factor: u16,           // 14bit
+    pub variable_fee_control: u32,       // 24bit, basis_point
+    pub protocol_share: u16,             // 14bit
+    pub max_volatility_accumulator: u32, // 20bit, basis_point
+    pub volatility_accumulator: u32,     // 20bit, basis_point
+    pub volatility_reference: u32,       // 20bit
+    pub index_reference: u32,            // 24bit
+    #[serde(with = "u64_string")]
+    pub time_of_last_update: u64,
+    pub oracle_index: u16,
+    pub active_index: u32, // 24bit
 }
 
 impl DlmmPairParameter {
-    pub fn get_total_fee(&self, bin_step: u16) -> u64 {
-        self.get_base_fee(bin_step) + self.get_variable_fee(bin_step)
+    pub fn get_total_fee(&self, bin_step: u16) -> Fee1e9 {
+        self.get_base_fee(bin_step)
+            .checked_add(self.get_variable_fee(bin_step))
+            .expect("ErrFeeOverflow")
     }
 
-    fn get_base_fee(&self, bin_step: u16) -> u64 {
+    pub fn get_base_fee(&self, bin_step: u16) -> Fee1e9 {
         // Base factor is in basis points: 10000
         // binStep is in basis points: 100000
         // 1e9
-        (self.base_factor as u64) * (bin_step as u64)
+        Fee1e9::new((self.base_factor as u64) * (bin_step as u64))
     }
 
-    fn get_variable_fee(&self, bin_step: u16) -> u64 {
+    pub fn get_variable_fee(&self, bin_step: u16) -> Fee1e9 {
+        Fee1e9::new(
+            self.variable_fee_1e9(bin_step, self.volatility_accumulator)
+                .to::<u64>(),
+        )
+    }
+
+    // Can't overflow, max(result) = ((2^32 * 2^16)^2 * 2^32 + 99) / 100 / 1e9 < 2^122
+    pub(crate) fn variable_fee_1e9(&self, bin_step: u16, volatility_accumulator: u32) -> U256 {
         if self.variable_fee_control != 0 {
             // The volatility accumulator is in basis points, binStep is in basis points,
             // and the variable fee control is in basis points, so the result is in 100e18th
-            let prod = U256::from(self.volatility_accumulator) * U256::from(bin_step);
-            ((prod * prod * U256::from(self.variable_fee_control) + U256::from(99))
+            let prod = U256::from(volatility_accumulator) * U256::from(bin_step);
+            (prod * prod * U256::from(self.variable_fee_control) + U256::from(99))
                 / U256::from(100)
-                / U256::from(1_000_000_000))
-            .to::<u64>()
+                / U256::from(1_000_000_000)
         } else {
-            0
+            U256::ZERO
         }
     }
 
@@ -259,7 +435,9 @@ impl DlmmPairParameter {
         self.update_volatility_accumulator(active_id);
     }
 
-    fn update_references(&mut self, timestamp: u64) {
+    // A timestamp before the last update is taken as the last update, which never goes back
+    pub(crate) fn update_references(&mut self, timestamp: u64) {
+        let timestamp = timestamp.max(self.time_of_last_update);
         let dt = timestamp - self.time_of_last_update;
         if dt >= self.filter_period as u64 {
             self.update_id_reference();
@@ -272,13 +450,9 @@ impl DlmmPairParameter {
         self.update_time_of_last_update(timestamp);
     }
 
-    fn update_volatility_accumulator(&mut self, active_id: u32) {
+    pub(crate) fn update_volatility_accumulator(&mut self, active_id: u32) {
         let id_reference = self.index_reference;
-        let delta_id = if active_id > id_reference {
-            active_id - id_reference
-        } else {
-            id_reference - active_id
-        };
+        let delta_id = active_id.abs_diff(id_reference);
         let mut vol_acc =
             self.volatility_reference + delta_id * (constants::BASIS_POINT_MAX as u32);
         let max_vol_acc = self.max_volatility_accumulator;
@@ -311,6 +485,29 @@ impl DlmmPairParameter {
     }
 }
 
+mod bins_by_id {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    use super::Bin;
+
+    pub fn serialize<S: Serializer>(
+        bins: &HashMap<u32, Bin>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut sorted: Vec<&Bin> = bins.values().collect();
+        sorted.sort_by_key(|bin| bin.storage_id);
+        serializer.collect_seq(sorted)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<u32, Bin>, D::Error> {
+        let bins = Vec::<Bin>::deserialize(deserializer)?;
+        Ok(bins.into_iter().map(|bin| (bin.storage_id, bin)).collect())
+    }
+}
+
 mod full_math_u64 {
     // ref: https://github.com/CetusProtocol/integer-mate/blob/4d09c8ba0527274983c6664a5d5def2e784d4692/sui/sources/full_math_u64.move#L2-L5
     pub fn mul_div_floor(num1: u64, num2: u64, denom: u64) -> u64 {
@@ -319,29 +516,28 @@ mod full_math_u64 {
     }
 }
 
-mod bin {
-    use crate::{constants, u128x128};
+pub(crate) mod bin {
+    use crate::{
+        constants,
+        units::{Fee1e9, PriceX128},
+    };
 
-    use super::{fee, price, uint_safe};
+    use super::{fee, uint_safe};
     use alloy_primitives::U256;
 
     pub fn get_amounts(
         reserve_x: u64,
         reserve_y: u64,
-        bin_step: u16,
-        total_fee: u64,
+        bin_price_q128: PriceX128,
+        total_fee: Fee1e9,
         swap_for_y: bool,
-        active_id: u32,
         amount_in_left: u64,
     ) -> (u64, u64, u64, u64, u64, u64) {
-        let bin_price_q128 = price::get_price_x128_from_storage_id(active_id, bin_step);
-
         let bin_reserve_out = if swap_for_y { reserve_y } else { reserve_x };
         let max_amount_in = if swap_for_y {
-            uint_safe::safe64(u128x128::to_u128x128(bin_reserve_out as u128, 0) / bin_price_q128)
+            uint_safe::safe64(bin_price_q128.y_to_x(bin_reserve_out))
         } else {
-            let (amount, _) = u128x128::from_u128x128(U256::from(bin_reserve_out) * bin_price_q128);
-            uint_safe::safe64(U256::from(amount))
+            uint_safe::safe64(bin_price_q128.x_to_y(bin_reserve_out))
         };
 
         let max_fee = fee::get_fee_amount(max_amount_in, total_fee);
@@ -353,15 +549,15 @@ mod bin {
             let fee = fee::get_fee_amount_from(amount_in_left, total_fee);
             let amount_in = amount_in_left - fee;
             let mut amount_out = if swap_for_y {
-                let (amount, _) = u128x128::from_u128x128(U256::from(amount_in) * bin_price_q128);
-                uint_safe::safe64(U256::from(amount))
+                uint_safe::safe64(bin_price_q128.x_to_y(amount_in))
             } else {
-                uint_safe::safe64(u128x128::to_u128x128(amount_in as u128, 0) / bin_price_q128)
+                uint_safe::safe64(bin_price_q128.y_to_x(amount_in))
             };
             if amount_out > bin_reserve_out {
                 amount_out = bin_reserve_out;
             };
-            (fee, amount_in, amount_out)
+            // the whole amount is spent, fee included
+            (fee, amount_in_left, amount_out)
         };
 
         let (
@@ -377,7 +573,7 @@ mod bin {
                     reserve_x + amount_in,
                     reserve_y - amount_out,
                     bin_price_q128
-                ) <= U256::from(constants::max_liquidity_per_bin()),
+                ) <= constants::MAX_LIQUIDITY_PER_BIN,
                 "ErrMaxLiquidityPerBinExceeded"
             );
             (amount_in, 0, 0, amount_out, fee, 0)
@@ -387,34 +583,32 @@ mod bin {
                     reserve_x - amount_out,
                     reserve_y + amount_in,
                     bin_price_q128
-                ) <= U256::from(constants::max_liquidity_per_bin()),
+                ) <= constants::MAX_LIQUIDITY_PER_BIN,
                 "ErrMaxLiquidityPerBinExceeded"
             );
             (0, amount_in, amount_out, 0, 0, fee)
         };
 
-        return (
+        (
             amounts_in_with_fees_x,
             amounts_in_with_fees_y,
             amounts_out_of_bin_x,
             amounts_out_of_bin_y,
             fee_x,
             fee_y,
-        );
+        )
     }
 
-    pub fn get_liquidity(amount_x: u64, amount_y: u64, price_q128: U256) -> U256 {
+    pub fn get_liquidity(amount_x: u64, amount_y: u64, price_q128: PriceX128) -> U256 {
         let mut liquidity = U256::ZERO;
         if amount_x > 0 {
-            liquidity = price_q128 * U256::from(amount_x);
-            assert!(
-                liquidity / U256::from(amount_x) == price_q128,
-                "ErrLiquidityOverflow"
-            );
+            let (product, overflow) = price_q128.x128().overflowing_mul(U256::from(amount_x));
+            assert!(!overflow, "ErrLiquidityOverflow");
+            liquidity = product;
         };
         if amount_y > 0 {
             let amount_y_256 = U256::from(amount_y) << constants::SCALE_OFFSET;
-            liquidity = liquidity + amount_y_256;
+            liquidity += amount_y_256;
             assert!(liquidity >= amount_y_256, "ErrLiquidityOverflow");
         };
         liquidity
@@ -422,36 +616,279 @@ mod bin {
 }
 
 mod fee {
-    use alloy_primitives::U256;
-
-    use crate::constants;
+    use crate::{constants, units::Fee1e9};
 
-    use super::uint_safe;
+    use super::{full_math_u64, uint_safe};
 
-    pub fn get_fee_amount_from(amount_with_fees: u64, total_fee: u64) -> u64 {
+    pub fn get_fee_amount_from(amount_with_fees: u64, total_fee: Fee1e9) -> u64 {
         verify_fee(total_fee);
 
         // Can't overflow, max(result) = (type(uint128).max * 0.1e18 + 1e18 - 1) / 1e18 < 2^128
-        let amount = (U256::from(amount_with_fees) * U256::from(total_fee)
-            + U256::from(constants::PRECISION)
-            - U256::from(1))
-            / U256::from(constants::PRECISION);
-
-        uint_safe::safe64(amount)
+        uint_safe::safe64(total_fee.fee_of(amount_with_fees))
     }
 
-    pub fn get_fee_amount(amount: u64, total_fee: u64) -> u64 {
+    pub fn get_fee_amount(amount: u64, total_fee: Fee1e9) -> u64 {
         verify_fee(total_fee);
 
-        let denominator = U256::from(constants::PRECISION as u64 - total_fee);
         // Can't overflow, max(result) = (type(uint128).max * 0.1e18 + (1e18 - 1)) / 0.9e18 < 2^128
-        let amount = (U256::from(amount) * U256::from(total_fee) + denominator - U256::from(1))
-            / denominator;
+        uint_safe::safe64(total_fee.fee_on(amount))
+    }
+
+    pub fn get_protocol_fee_amount(fee_amount: u64, protocol_share: u16) -> u64 {
+        full_math_u64::mul_div_floor(
+            fee_amount,
+            protocol_share as u64,
+            constants::BASIS_POINT_MAX as u64,
+        )
+    }
+
+    pub fn verify_fee(fee: Fee1e9) {
+        assert!(fee.is_valid(), "ErrFeeTooLarge");
+    }
+}
+
+// Two-sided bins of 1_000_000 X and Y around real id 0, shared with the benches
+#[cfg(any(test, feature = "test-utils"))]
+#[doc(hidden)]
+pub fn test_pair(bin_step: u16, bins_per_side: u32) -> DlmmPair {
+    let active_id = price::get_storage_id_from_real_id(0);
+    let params = DlmmPairParameter {
+        base_factor: 10000,
+        filter_period: 30,
+        decay_period: 600,
+        reduction_factor: 5000,
+        variable_fee_control: 40000,
+        protocol_share: 1000,
+        max_volatility_accumulator: 350000,
+        index_reference: active_id,
+        active_index: active_id,
+        ..Default::default()
+    };
+    let bins = (active_id - bins_per_side..=active_id + bins_per_side)
+        .map(|storage_id| Bin {
+            storage_id,
+            price_q128: price::get_price_x128_from_storage_id(storage_id, bin_step),
+            reserve_x: 1_000_000,
+            reserve_y: 1_000_000,
+            ..Default::default()
+        })
+        .collect();
+    DlmmPair::new(bin_step, params, bins)
+}
+
+// Same as test_pair, with Y only in the bins below the active one and X only above
+#[cfg(test)]
+pub(crate) fn test_one_sided_pair(bin_step: u16, bins_per_side: u32) -> DlmmPair {
+    let mut pair = test_pair(bin_step, bins_per_side);
+    let active_id = pair.active_id();
+    for bin in pair.bins.values_mut() {
+        if bin.storage_id > active_id {
+            bin.reserve_y = 0;
+        } else if bin.storage_id < active_id {
+            bin.reserve_x = 0;
+        };
+    }
+    pair
+}
+
+#[test]
+fn test_price_table_quotes_unchanged() {
+    let mut pair = test_pair(25, 200);
+    let out = get_swap_out(&pair, 50_000_000, true, 1_000_000);
+    let inp = get_swap_in(&pair, 50_000_000, false, 1_000_000);
+
+    pair.enable_price_table(100);
+    let out_table = get_swap_out(&pair, 50_000_000, true, 1_000_000);
+    let inp_table = get_swap_in(&pair, 50_000_000, false, 1_000_000);
+    assert!(out.amount_out > 0 && out.fee > 0);
+    assert!(out == out_table);
+    assert!(inp == inp_table);
+}
+
+#[test]
+fn test_native_api() {
+    let pair = test_pair(25, 10);
+    assert!(pair.bin_step() == 25);
+    assert!(pair.bins().count() == 21);
+    let active = pair.bin(pair.active_id()).unwrap();
+    assert!(active.price_q128 == PriceX128::ONE);
+    assert!(pair.bin(pair.active_id() + 11).is_none());
+
+    // base fee: base_factor * bin_step
+    assert!(pair.params().get_base_fee(pair.bin_step()) == Fee1e9::new(250_000));
+    assert!(pair.params().get_total_fee(pair.bin_step()) == Fee1e9::new(250_000));
+
+    // fits in the active bin at price 1, the whole amount is spent fee included
+    let quote = get_swap_out(&pair, 100_000, false, 0);
+    assert!(quote.amount_in_left == 0 && quote.fee > 0);
+    assert!(quote.amount_out + quote.fee <= 100_000);
+
+    // selling either side for more than a bin holds gets a worse price, not a better one
+    for swap_for_y in [true, false] {
+        let quote = get_swap_out(&pair, 5_000_000, swap_for_y, 0);
+        assert!(quote.amount_in_left == 0 && quote.amount_out < 5_000_000 - quote.fee);
+    }
+}
+
+#[test]
+fn test_swap_walks_to_the_output_token() {
+    // X in goes to the lower ids, which hold Y, Y in to the higher ids, which hold X
+    let mut pair = test_pair(25, 10);
+    let active_id = pair.active_id();
+    assert!(pair.get_next_non_empty_bin_internal(true, active_id) == (active_id - 1, true));
+    assert!(pair.get_next_non_empty_bin_internal(false, active_id) == (active_id + 1, true));
+
+    for bin in pair.bins.values_mut() {
+        if bin.storage_id > active_id {
+            bin.reserve_y = 0;
+        } else if bin.storage_id < active_id {
+            bin.reserve_x = 0;
+        };
+    }
+    // the active bin holds 1_000_000 of each, the rest comes from the bins past it. Walking
+    // away from them, selling 3_000_000 X stopped at the active bin: 1_000_000 Y out, fee 251,
+    // 1_999_749 left
+    let quote = get_swap_out(&pair, 3_000_000, true, 0);
+    assert!(
+        quote
+            == SwapOutResult {
+                amount_in_left: 0,
+                amount_out: 2_991_659,
+                fee: 876
+            }
+    );
+    for swap_for_y in [true, false] {
+        let quote = get_swap_out(&pair, 3_000_000, swap_for_y, 0);
+        assert!(quote.amount_in_left == 0 && quote.amount_out > 2_000_000);
+        let quote = get_swap_in(&pair, 2_500_000, swap_for_y, 0);
+        assert!(quote.amount_out_left == 0 && quote.amount_in > 2_500_000);
+    }
+}
 
-        uint_safe::safe64(amount)
+#[test]
+fn test_swap_partial_fill_spends_the_fee() {
+    // 100_000 fits in the active bin at price 1: the fee comes out of the amount in and
+    // nothing is left to carry into the next bin. Counting only the amount net of fee as
+    // spent, the 25 of fee went on to the next bin: 99_998 out, fee 26, 1 left
+    let pair = test_pair(25, 10);
+    for swap_for_y in [true, false] {
+        let quote = get_swap_out(&pair, 100_000, swap_for_y, 0);
+        assert!(
+            quote
+                == SwapOutResult {
+                    amount_in_left: 0,
+                    amount_out: 100_000 - 25,
+                    fee: 25
+                }
+        );
     }
+}
+
+#[test]
+fn test_update_references_stale_timestamp() {
+    let mut params = DlmmPairParameter {
+        filter_period: 30,
+        decay_period: 600,
+        index_reference: 100,
+        active_index: 105,
+        volatility_reference: 20_000,
+        volatility_accumulator: 50_000,
+        max_volatility_accumulator: 350_000,
+        time_of_last_update: 1_700_000_000,
+        ..Default::default()
+    };
+    params.update_volatility_parameters(105, 1_600_000_000);
+    // nothing elapsed, the references are kept
+    assert!(params.time_of_last_update == 1_700_000_000);
+    assert!(params.index_reference == 100 && params.volatility_reference == 20_000);
+    assert!(params.volatility_accumulator == 20_000 + 5 * constants::BASIS_POINT_MAX as u32);
+}
 
-    pub fn verify_fee(fee: u64) {
-        assert!(fee <= constants::MAX_FEE, "ErrFeeTooLarge");
+#[test]
+fn test_serde_sdk_layout() {
+    // as the SDK returns EventBin / EventPairParams, numbers or strings and the extra real_bin_id
+    let bin: Bin = serde_json::from_str(
+        r#"{
+            "storage_id": "8388608",
+            "price_q128": "340282366920938463463374607431768211456",
+            "reserve_x": 1000,
+            "reserve_y": "2000",
+            "staked_liquidity": "0",
+            "staked_lp_amount": "0",
+            "fee_x": "3",
+            "fee_y": 4,
+            "fee_growth_x": "5",
+            "fee_growth_y": "0",
+            "rewarder_growth": ["7", 8],
+            "distribution_growth": "0",
+            "distribution_last_updated": "1700000000",
+            "real_bin_id": 0
+        }"#,
+    )
+    .unwrap();
+    assert!(bin.storage_id == 8388608 && bin.price_q128 == PriceX128::ONE);
+    assert!(bin.reserve_x == 1000 && bin.reserve_y == 2000 && bin.fee_x == 3 && bin.fee_y == 4);
+    assert!(bin.fee_growth_x == U256::from(5));
+    assert!(bin.rewarder_growth == vec![U256::from(7), U256::from(8)]);
+    assert!(bin.distribution_last_updated == 1_700_000_000);
+
+    let json = serde_json::to_value(&bin).unwrap();
+    assert!(json["storage_id"] == 8388608);
+    assert!(json["reserve_y"] == "2000");
+    assert!(json["price_q128"] == "340282366920938463463374607431768211456");
+    assert!(json["rewarder_growth"] == serde_json::json!(["7", "8"]));
+
+    let params = DlmmPairParameter {
+        time_of_last_update: 1_700_000_000,
+        active_index: 8388608,
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&params).unwrap();
+    assert!(json["time_of_last_update"] == "1700000000");
+    assert!(json["active_index"] == 8388608);
+    assert!(serde_json::from_value::<DlmmPairParameter>(json).unwrap() == params);
+}
+
+#[test]
+fn test_serde_snapshot_round_trip() {
+    let mut pair = test_pair(25, 50);
+    pair.enable_price_table(10);
+
+    let json = serde_json::to_string(&pair).unwrap();
+    let from_json: DlmmPair = serde_json::from_str(&json).unwrap();
+    let bytes = pair.to_bytes();
+    let from_bytes = DlmmPair::from_bytes(&bytes).unwrap();
+    assert!(bytes.len() < json.len());
+    assert!(DlmmPair::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    for snapshot in [from_json, from_bytes] {
+        assert!(snapshot.bin_step() == pair.bin_step() && snapshot.params() == pair.params());
+        assert!(snapshot.bins == pair.bins && snapshot.price_table.is_none());
+        assert!(
+            get_swap_out(&snapshot, 5_000_000, true, 0) == get_swap_out(&pair, 5_000_000, true, 0)
+        );
     }
 }
+
+#[test]
+fn test_from_bcs() {
+    let pair = test_pair(25, 2);
+    let params_bytes = bcs::encode_pair_parameter(pair.params());
+
+    let mut sorted: Vec<&Bin> = pair.bins().collect();
+    sorted.sort_by_key(|bin| bin.storage_id);
+    let (first, rest) = sorted.split_at(2);
+    let page = |bins: &[&Bin]| {
+        let mut bytes = vec![bins.len() as u8];
+        for bin in bins {
+            bcs::encode_bin(bin, &mut bytes);
+        }
+        bytes
+    };
+
+    let mut decoded = DlmmPair::from_bcs(25, &params_bytes, &page(first)).unwrap();
+    assert!(decoded.bins().count() == 2);
+    decoded.insert_bins_bcs(&page(rest)).unwrap();
+    assert!(decoded.params() == pair.params() && decoded.bins == pair.bins);
+    assert!(DlmmPair::from_bcs(25, &params_bytes[1..], &page(first)).is_err());
+}
@@ -1,5 +1,4 @@
 use alloy_primitives::U256;
-use std::str::FromStr;
 
 use crate::bit_math;
 
@@ -7,25 +6,17 @@ const FIX_POINT_BITS: u8 = 128;
 const INTEGER_BITS: u8 = 128;
 const LOG_SCALE_OFFSET: u8 = 127;
 
-fn log_scale() -> U256 {
-    U256::from(1) << LOG_SCALE_OFFSET
-}
-
-// const LOG_SCALE_SQUARED: U256 = (1 << 127) * (1 << 127);
-fn log_scale_squared() -> U256 {
-    (U256::from(1) << 127) * (U256::from(1) << 127)
-}
-
-fn max_u256() -> U256 {
-    U256::from_str("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap()
-}
-
-pub fn from_u128x128(x: U256) -> (u128, u128) {
-    (
-        (x >> U256::from(128)).to::<u128>(),
-        (x & (U256::from(1) << U256::from(128) - U256::from(1))).to::<u128>(),
-    )
-}
+// 1 << 127
+const LOG_SCALE: U256 = U256::from_limbs([0, 1 << 63, 0, 0]);
+// (1 << 127) * (1 << 127)
+const LOG_SCALE_SQUARED: U256 = U256::from_limbs([0, 0, 0, 1 << 62]);
+// 1 << 128
+const ONE: U256 = U256::from_limbs([0, 0, 1, 0]);
+// (1 << 128) - 1
+const MAX_FRACTION: U256 = U256::from_limbs([u64::MAX, u64::MAX, 0, 0]);
+// the largest 128.128 value, (1 << (FIX_POINT_BITS + INTEGER_BITS)) - 1
+const MAX_U: U256 = U256::MAX;
+const _: () = assert!(FIX_POINT_BITS as u16 + INTEGER_BITS as u16 == 256);
 
 pub fn to_u128x128(x: u128, decimals: u8) -> U256 {
     (U256::from(x) << U256::from(128)) / U256::from(10).pow(U256::from(decimals))
@@ -41,27 +32,27 @@ pub fn log2(mut x: U256) -> (U256, bool) {
     };
 
     // drop the least significant bit of the fraction part
-    x = x >> 1;
+    x >>= 1;
 
-    let sign_positive = if x >= log_scale() {
+    let sign_positive = if x >= LOG_SCALE {
         true
     } else {
-        x = log_scale_squared() / x;
+        x = LOG_SCALE_SQUARED / x;
         false
     };
 
     let n = bit_math::most_significant_bit(U256::from(x >> LOG_SCALE_OFFSET));
     let mut result = U256::from(n) << LOG_SCALE_OFFSET;
     let mut y = x >> n;
-    if y != log_scale() {
+    if y != LOG_SCALE {
         let mut delta = U256::from(1) << (LOG_SCALE_OFFSET - 1);
         while delta > U256::ZERO {
             y = (y * y) >> LOG_SCALE_OFFSET;
             if y >= (U256::from(1) << (LOG_SCALE_OFFSET + 1)) {
-                result = result + delta;
-                y = y >> 1;
+                result += delta;
+                y >>= 1;
             };
-            delta = delta >> 1;
+            delta >>= 1;
         }
     };
 
@@ -69,27 +60,35 @@ pub fn log2(mut x: U256) -> (U256, bool) {
 }
 
 pub fn pow(x: U256, y: i32) -> U256 {
+    match try_pow(x, y) {
+        Some(result) => result,
+        None => panic!("ErrPowUnderflow"),
+    }
+}
+
+// Same as `pow`, but returns None instead of panicking when y is too big or x^y underflowed
+pub fn try_pow(x: U256, y: i32) -> Option<U256> {
     let mut invert = false;
 
     if x == U256::ZERO {
-        return U256::ZERO;
+        return Some(U256::ZERO);
     };
 
     if y == 0i32 {
-        return U256::from(1) << FIX_POINT_BITS;
+        return Some(ONE);
     };
 
-    let abs_y = y.abs() as u128;
+    let abs_y = y.unsigned_abs() as u128;
     if y.is_negative() {
         invert = !invert;
     };
     let mut result = U256::ZERO;
     if abs_y < 0x100000 {
-        result = U256::from(1) << FIX_POINT_BITS;
+        result = ONE;
 
         let mut squared = x;
-        if x > ((U256::from(1) << FIX_POINT_BITS) - U256::from(1)) {
-            squared = max_u() / squared;
+        if x > MAX_FRACTION {
+            squared = MAX_U / squared;
             invert = !invert;
         };
 
@@ -176,18 +175,16 @@ pub fn pow(x: U256, y: i32) -> U256 {
 
     // revert if y is too big or if x^y underflowed
     if result == U256::ZERO {
-        panic!("ErrPowUnderflow")
+        return None;
     };
 
-    if invert { max_u() / result } else { result }
+    Some(if invert { MAX_U / result } else { result })
 }
 
-fn max_u() -> U256 {
-    let bits = (FIX_POINT_BITS as u16) + (INTEGER_BITS as u16);
-    assert!(bits <= 256);
-    if bits == 256 {
-        max_u256()
-    } else {
-        (U256::from(1) << (FIX_POINT_BITS + INTEGER_BITS)) - U256::from(1)
-    }
+#[test]
+fn test_constants() {
+    assert!(LOG_SCALE == U256::from(1) << LOG_SCALE_OFFSET);
+    assert!(LOG_SCALE_SQUARED == (U256::from(1) << 127) * (U256::from(1) << 127));
+    assert!(ONE == U256::from(1) << FIX_POINT_BITS);
+    assert!(MAX_FRACTION == ONE - U256::from(1));
 }
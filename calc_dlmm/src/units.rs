@@ -0,0 +1,611 @@
+use alloy_primitives::{U256, U512};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use crate::{constants, price::Rounding, u128x128::to_u128x128, uint_safe};
+
+// 128.128-binary fixed-point price of token Y per token X, in raw token units
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PriceX128(U256);
+
+impl PriceX128 {
+    pub const ZERO: PriceX128 = PriceX128(U256::ZERO);
+    pub const ONE: PriceX128 = PriceX128(U256::from_limbs([0, 0, 1, 0]));
+    pub const MAX: PriceX128 = PriceX128(U256::MAX);
+
+    pub const fn from_x128(price_x128: U256) -> Self {
+        PriceX128(price_x128)
+    }
+
+    pub const fn x128(self) -> U256 {
+        self.0
+    }
+
+    pub fn checked_mul(self, rhs: PriceX128) -> Option<PriceX128> {
+        let product = (U512::from(self.0) * U512::from(rhs.0)) >> constants::SCALE_OFFSET;
+        fit_u256(product).map(PriceX128)
+    }
+
+    pub fn checked_div(self, rhs: PriceX128) -> Option<PriceX128> {
+        if rhs.0 == U256::ZERO {
+            return None;
+        };
+        let quotient = (U512::from(self.0) << constants::SCALE_OFFSET) / U512::from(rhs.0);
+        fit_u256(quotient).map(PriceX128)
+    }
+
+    // Price of token X per token Y
+    pub fn reciprocal(self) -> Option<PriceX128> {
+        PriceX128::ONE.checked_div(self)
+    }
+
+    // Amount of Y worth `amount_x` of X, rounded down. Can't overflow, max(result) < 2^192
+    pub fn x_to_y(self, amount_x: u64) -> U256 {
+        let amount_y = (U512::from(amount_x) * U512::from(self.0)) >> constants::SCALE_OFFSET;
+        U256::from(amount_y)
+    }
+
+    // Amount of X worth `amount_y` of Y, rounded down. The price must not be zero
+    pub fn y_to_x(self, amount_y: u64) -> U256 {
+        self.checked_y_to_x(amount_y).expect("ErrPriceUnderflow")
+    }
+
+    // Same as y_to_x, None at the price zero
+    pub fn checked_y_to_x(self, amount_y: u64) -> Option<U256> {
+        if self.0 == U256::ZERO {
+            return None;
+        };
+        Some((U256::from(amount_y) << constants::SCALE_OFFSET) / self.0)
+    }
+
+    // price: decimal with constants::PRECISION decimals
+    pub fn from_precision_decimal(price: U256) -> PriceX128 {
+        PriceX128(to_u128x128(
+            uint_safe::safe128(price),
+            constants::precision_n(),
+        ))
+    }
+
+    // Return the price as a decimal with constants::PRECISION decimals, rounded down
+    pub fn to_precision_decimal(self) -> U256 {
+        let price =
+            (U512::from(self.0) * U512::from(constants::precision())) >> constants::SCALE_OFFSET;
+        U256::from(uint_safe::safe128(fit_u256(price).expect("ErrSafe128")))
+    }
+
+    // price: decimal string of token Y per token X in UI units, e.g. "1.2345" or "3e-7"
+    // Return None if the price does not fit a 128.128 number
+    pub fn try_from_ui_decimal(
+        price: &str,
+        decimals_x: u8,
+        decimals_y: u8,
+        rounding: Rounding,
+    ) -> Option<PriceX128> {
+        let (num, den) = decimal_price_to_x128_fraction(price, decimals_x, decimals_y)?;
+        let price = match rounding {
+            Rounding::Up => (num + den - U512::from(1)) / den,
+            Rounding::Down | Rounding::Nearest => num / den,
+        };
+        fit_u256(price).map(PriceX128)
+    }
+
+    // Return the price as a decimal string of token Y per token X in UI units,
+    // with exactly `precision` fractional digits
+    pub fn to_ui_decimal(
+        self,
+        decimals_x: u8,
+        decimals_y: u8,
+        precision: u8,
+        rounding: Rounding,
+    ) -> String {
+        assert!(
+            precision as u16 + decimals_x as u16 <= 76 && decimals_y <= 76,
+            "ErrPrecisionTooLarge"
+        );
+        let num = U512::from(self.0) * pow10(precision + decimals_x);
+        let den = pow10(decimals_y) << constants::SCALE_OFFSET;
+        let scaled = match rounding {
+            Rounding::Down => num / den,
+            Rounding::Up => (num + den - U512::from(1)) / den,
+            Rounding::Nearest => ((num << 1) + den) / (den << 1),
+        };
+        format_decimal(scaled, precision)
+    }
+}
+
+// Parses the 128.128 price as an int string
+impl FromStr for PriceX128 {
+    type Err = <U256 as FromStr>::Err;
+
+    fn from_str(price_x128: &str) -> Result<Self, Self::Err> {
+        U256::from_str(price_x128).map(PriceX128)
+    }
+}
+
+impl fmt::Display for PriceX128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for PriceX128 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        u256_string::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PriceX128 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u256_string::deserialize(deserializer).map(PriceX128)
+    }
+}
+
+// Fee rate with constants::PRECISION (1e9) precision, 1e7 is 1%
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Fee1e9(u64);
+
+impl Fee1e9 {
+    pub const ZERO: Fee1e9 = Fee1e9(0);
+    pub const MAX: Fee1e9 = Fee1e9(constants::MAX_FEE);
+
+    pub const fn new(fee: u64) -> Self {
+        Fee1e9(fee)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Fee1e9) -> Option<Fee1e9> {
+        self.0.checked_add(rhs.0).map(Fee1e9)
+    }
+
+    pub fn saturating_add(self, rhs: Fee1e9) -> Fee1e9 {
+        Fee1e9(self.0.saturating_add(rhs.0))
+    }
+
+    pub fn checked_sub(self, rhs: Fee1e9) -> Option<Fee1e9> {
+        self.0.checked_sub(rhs.0).map(Fee1e9)
+    }
+
+    // Part `bps` basis points of the fee, rounded down
+    pub fn mul_bps(self, bps: u16) -> Fee1e9 {
+        Fee1e9((self.0 as u128 * bps as u128 / constants::BASIS_POINT_MAX as u128) as u64)
+    }
+
+    // Fee taken from `amount_with_fees`, rounded up
+    pub fn fee_of(self, amount_with_fees: u64) -> U256 {
+        (U256::from(amount_with_fees) * U256::from(self.0) + U256::from(constants::PRECISION)
+            - U256::from(1))
+            / U256::from(constants::PRECISION)
+    }
+
+    // Fee to add to `amount` so that it is this rate of the total, rounded up. The rate must
+    // be below 100%
+    pub fn fee_on(self, amount: u64) -> U256 {
+        let denominator = U256::from(constants::PRECISION as u64 - self.0);
+        (U256::from(amount) * U256::from(self.0) + denominator - U256::from(1)) / denominator
+    }
+
+    // Fee rate at or below constants::MAX_FEE
+    pub fn is_valid(self) -> bool {
+        self <= Fee1e9::MAX
+    }
+}
+
+impl fmt::Display for Fee1e9 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// U256 as a decimal string in human readable formats, as the SDK events do
+pub(crate) mod u256_string {
+    use alloy_primitives::U256;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+    use std::{fmt, str::FromStr};
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(value)
+        } else {
+            serde::Serialize::serialize(value, serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(DecimalVisitor)
+        } else {
+            U256::deserialize(deserializer)
+        }
+    }
+
+    struct DecimalVisitor;
+
+    impl de::Visitor<'_> for DecimalVisitor {
+        type Value = U256;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a u256 as a decimal string or an integer")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<U256, E> {
+            Ok(U256::from(value))
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<U256, E> {
+            U256::from_str(value).map_err(E::custom)
+        }
+    }
+}
+
+// Vector of U256 as decimal strings in human readable formats
+pub(crate) mod u256_string_vec {
+    use alloy_primitives::U256;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    struct Decimal(#[serde(with = "super::u256_string")] U256);
+
+    pub fn serialize<S: Serializer>(values: &[U256], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(values.iter().map(U256::to_string))
+        } else {
+            values.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<U256>, D::Error> {
+        if deserializer.is_human_readable() {
+            let values = Vec::<Decimal>::deserialize(deserializer)?;
+            Ok(values.into_iter().map(|Decimal(value)| value).collect())
+        } else {
+            Vec::<U256>::deserialize(deserializer)
+        }
+    }
+}
+
+// u64 as a decimal string in human readable formats, as the SDK events do
+pub(crate) mod u64_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(value)
+        } else {
+            serializer.serialize_u64(*value)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(super::IntVisitor::<u64>::new())
+        } else {
+            u64::deserialize(deserializer)
+        }
+    }
+}
+
+// u32 the SDK types as `number | string`, written as a number
+pub(crate) mod u32_number {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(super::IntVisitor::<u32>::new())
+        } else {
+            u32::deserialize(deserializer)
+        }
+    }
+}
+
+// u128 / i128 totals as a decimal string in human readable formats, JS numbers can't hold them
+pub(crate) mod int_string {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::{fmt::Display, str::FromStr};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display + Serialize,
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_str(value)
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: TryFrom<u64> + FromStr + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(super::IntVisitor::<T>::new())
+        } else {
+            T::deserialize(deserializer)
+        }
+    }
+}
+
+// Vector of u128 / u64 as decimal strings in human readable formats
+pub(crate) mod int_string_vec {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::{fmt::Display, str::FromStr};
+
+    #[derive(Deserialize)]
+    #[serde(bound(deserialize = "T: TryFrom<u64> + FromStr + Deserialize<'de>"))]
+    struct Int<T>(#[serde(with = "super::int_string")] T);
+
+    pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display + Serialize,
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(values.iter().map(T::to_string))
+        } else {
+            values.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: TryFrom<u64> + FromStr + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let values = Vec::<Int<T>>::deserialize(deserializer)?;
+            Ok(values.into_iter().map(|Int(value)| value).collect())
+        } else {
+            Vec::<T>::deserialize(deserializer)
+        }
+    }
+}
+
+// Accepts an integer or a decimal string
+struct IntVisitor<T>(PhantomData<T>);
+
+impl<T> IntVisitor<T> {
+    fn new() -> Self {
+        IntVisitor(PhantomData)
+    }
+}
+
+impl<T: TryFrom<u64> + FromStr> de::Visitor<'_> for IntVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a {} as a decimal string or an integer",
+            std::any::type_name::<T>()
+        )
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<T, E> {
+        T::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        value
+            .parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
+
+fn fit_u256(value: U512) -> Option<U256> {
+    if value >> 256 == U512::ZERO {
+        Some(U256::from(value))
+    } else {
+        None
+    }
+}
+
+// Return the 128.128 price as an exact fraction (num / den), scaled by the token decimals.
+// None when the mantissa has more than 77 significant digits or num overflows, the price
+// is then >= 2^384 and can't fit a 128.128 number
+fn decimal_price_to_x128_fraction(
+    price: &str,
+    decimals_x: u8,
+    decimals_y: u8,
+) -> Option<(U512, U512)> {
+    let (mantissa, exp10) = parse_decimal(price)?;
+    assert!(mantissa > U256::ZERO, "ErrInvalidPrice");
+
+    // mantissa < 2^256, num < 2^384 < 10^116
+    let num = U512::from(mantissa) << constants::SCALE_OFFSET;
+    let exp10 = exp10 + decimals_y as i64 - decimals_x as i64;
+    if exp10 >= 0 {
+        let exp10 = u8::try_from(exp10).ok().filter(|exp10| *exp10 <= 154)?;
+        Some((num.checked_mul(pow10(exp10))?, U512::from(1)))
+    } else {
+        // Any den above num floors to 0 and ceils to 1, clamp it to stay within U512
+        Some((num, pow10(exp10.unsigned_abs().min(116) as u8)))
+    }
+}
+
+// Parse a non-negative decimal string into (mantissa, exponent) with value = mantissa * 10^exponent.
+// Trailing zeros are folded into the exponent, None if more than 77 significant digits remain
+fn parse_decimal(value: &str) -> Option<(U256, i64)> {
+    let value = value.trim();
+    let value = value.strip_prefix('+').unwrap_or(value);
+    let (number, exp10) = match value.find(['e', 'E']) {
+        Some(i) => (
+            &value[..i],
+            i32::from_str(&value[i + 1..]).expect("ErrInvalidPrice"),
+        ),
+        None => (value, 0),
+    };
+    let (integer, fraction) = match number.find('.') {
+        Some(i) => (&number[..i], &number[i + 1..]),
+        None => (number, ""),
+    };
+    assert!(
+        !(integer.is_empty() && fraction.is_empty())
+            && integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|c| c.is_ascii_digit()),
+        "ErrInvalidPrice"
+    );
+
+    let digits = [integer, fraction].concat();
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return Some((U256::ZERO, 0));
+    }
+    let mantissa = U256::from_str_radix(significant, 10).ok()?;
+    let trailing_zeros = (digits.len() - significant.len()) as i64;
+    Some((
+        mantissa,
+        exp10 as i64 + trailing_zeros - fraction.len() as i64,
+    ))
+}
+
+fn format_decimal(value: U512, precision: u8) -> String {
+    let digits = value.to_string();
+    let precision = precision as usize;
+    if precision == 0 {
+        return digits;
+    };
+    let digits = format!("{:0>width$}", digits, width = precision + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - precision);
+    format!("{}.{}", integer, fraction)
+}
+
+fn pow10(exp: u8) -> U512 {
+    U512::from(10).pow(U512::from(exp))
+}
+
+#[test]
+fn test_parse_decimal() {
+    assert!(parse_decimal("1.2345") == Some((U256::from(12345), -4)));
+    assert!(parse_decimal("0.000120") == Some((U256::from(12), -5)));
+    assert!(parse_decimal("42") == Some((U256::from(42), 0)));
+    assert!(parse_decimal(".5") == Some((U256::from(5), -1)));
+    assert!(parse_decimal("3e-7") == Some((U256::from(3), -7)));
+    assert!(parse_decimal("1.5E+3") == Some((U256::from(15), 2)));
+    assert!(parse_decimal("0") == Some((U256::ZERO, 0)));
+
+    // Trailing zeros beyond 77 digits fold into the exponent
+    let zeros = "0".repeat(100);
+    assert!(parse_decimal(&format!("1.{zeros}")) == Some((U256::from(1), 0)));
+    assert!(parse_decimal(&format!("1{zeros}")) == Some((U256::from(1), 100)));
+    assert!(parse_decimal(&format!("1.{zeros}1")).is_none());
+
+    // The exponent is widened, not wrapped
+    assert!(parse_decimal("1.5e-2147483648") == Some((U256::from(15), i32::MIN as i64 - 1)));
+    assert!(parse_decimal("10e2147483647") == Some((U256::from(1), i32::MAX as i64 + 1)));
+}
+
+#[test]
+fn test_ui_decimal_out_of_range() {
+    // Too small to fit rounds to zero, or to the smallest price rounding up
+    for price in ["1e-80", "1e-2147483648"] {
+        assert!(
+            PriceX128::try_from_ui_decimal(price, 6, 6, Rounding::Down) == Some(PriceX128::ZERO)
+        );
+        assert!(
+            PriceX128::try_from_ui_decimal(price, 6, 6, Rounding::Up)
+                == Some(PriceX128::from_x128(U256::from(1)))
+        );
+    }
+    // Too large to fit
+    for price in ["1e80", "1e2147483647"] {
+        assert!(PriceX128::try_from_ui_decimal(price, 6, 6, Rounding::Down).is_none());
+    }
+    // Trailing zeros don't overflow the mantissa
+    let price = format!("1.{}", "0".repeat(100));
+    assert!(PriceX128::try_from_ui_decimal(&price, 6, 6, Rounding::Down) == Some(PriceX128::ONE));
+    assert!(PriceX128::try_from_ui_decimal(&format!("{price}1"), 6, 6, Rounding::Down).is_none());
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidPrice")]
+fn test_parse_decimal_invalid() {
+    parse_decimal("-1.5");
+}
+
+#[test]
+fn test_price_x128_arithmetic() {
+    let two = PriceX128::from_x128(U256::from(2) << 128);
+    let half = two.reciprocal().unwrap();
+    assert!(half == PriceX128::from_x128(U256::from(1) << 127));
+    assert!(two.checked_mul(half) == Some(PriceX128::ONE));
+    assert!(PriceX128::ONE.checked_div(half) == Some(two));
+    assert!(PriceX128::MAX.checked_mul(two).is_none());
+    assert!(PriceX128::ONE.checked_div(PriceX128::ZERO).is_none());
+    assert!(PriceX128::ZERO.reciprocal().is_none());
+
+    assert!(two.x_to_y(21) == U256::from(42));
+    assert!(two.y_to_x(43) == U256::from(21));
+    assert!(PriceX128::ZERO.checked_y_to_x(1).is_none());
+    assert!(PriceX128::MAX.x_to_y(1) == U256::MAX >> 128);
+}
+
+#[test]
+fn test_price_x128_decimal_conversions() {
+    let price = PriceX128::from_precision_decimal(U256::from(1_100_000_000u64));
+    assert!(price.to_precision_decimal() == U256::from(1_099_999_999u64));
+    assert!(price.to_ui_decimal(9, 9, 4, Rounding::Nearest) == "1.1000");
+
+    let price = PriceX128::try_from_ui_decimal("1.5", 6, 9, Rounding::Down).unwrap();
+    assert!(price == PriceX128::from_x128(U256::from(1500) << 128));
+    assert!(price.to_ui_decimal(6, 9, 2, Rounding::Down) == "1.50");
+
+    let third = PriceX128::try_from_ui_decimal("0.3", 0, 0, Rounding::Down).unwrap();
+    let third_up = PriceX128::try_from_ui_decimal("0.3", 0, 0, Rounding::Up).unwrap();
+    assert!(third_up.x128() == third.x128() + U256::from(1));
+    assert!(PriceX128::try_from_ui_decimal("1e70", 0, 6, Rounding::Down).is_none());
+    // a 77 digit mantissa at 10^76
+    let long = format!("{}e76", "9".repeat(77));
+    assert!(PriceX128::try_from_ui_decimal(&long, 0, 0, Rounding::Up).is_none());
+}
+
+#[test]
+#[should_panic(expected = "ErrSafe128")]
+fn test_price_x128_precision_decimal_overflow() {
+    PriceX128::MAX.to_precision_decimal();
+}
+
+#[test]
+fn test_serde() {
+    let price = PriceX128::from_x128(U256::from(3) << 127);
+    let json = serde_json::to_string(&price).unwrap();
+    assert!(json == "\"510423550381407695195061911147652317184\"");
+    assert!(serde_json::from_str::<PriceX128>(&json).unwrap() == price);
+    assert!(
+        serde_json::from_str::<PriceX128>("42").unwrap() == PriceX128::from_x128(U256::from(42))
+    );
+
+    assert!(serde_json::to_string(&Fee1e9::new(3_000_000)).unwrap() == "3000000");
+    assert!(serde_json::from_str::<Fee1e9>("3000000").unwrap() == Fee1e9::new(3_000_000));
+}
+
+#[test]
+#[should_panic(expected = "ErrPriceUnderflow")]
+fn test_y_to_x_zero_price() {
+    PriceX128::ZERO.y_to_x(1);
+}
+
+#[test]
+fn test_fee1e9_amounts() {
+    // 1%
+    let fee = Fee1e9::new(10_000_000);
+    assert!(fee.mul_bps(2500) == Fee1e9::new(2_500_000));
+    assert!(fee.fee_of(1_000) == U256::from(10) && fee.fee_of(1_001) == U256::from(11));
+    // 990 + 10 = 1000, 10 is 1% of it
+    assert!(fee.fee_on(990) == U256::from(10) && fee.fee_on(991) == U256::from(11));
+    assert!(Fee1e9::MAX.fee_of(u64::MAX) < U256::from(u64::MAX));
+}
@@ -0,0 +1,364 @@
+use alloy_primitives::U256;
+use std::fmt;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    constants, price,
+    swap_result::{Bin, DlmmPair, DlmmPairParameter},
+    units::{Fee1e9, PriceX128},
+};
+
+// A broken invariant of a DlmmPair snapshot, e.g. from bad or stale RPC data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    // outside the ids supported for the bin step
+    ActiveIndexOutOfRange {
+        active_index: u32,
+    },
+    ActiveBinMissing {
+        active_index: u32,
+    },
+    StorageIdOutOfRange {
+        storage_id: u32,
+    },
+    // bins above the active one hold only X, bins below only Y
+    WrongSideReserve {
+        storage_id: u32,
+        reserve_x: u64,
+        reserve_y: u64,
+    },
+    PriceMismatch {
+        storage_id: u32,
+        expected: PriceX128,
+        actual: PriceX128,
+    },
+    LiquidityTooLarge {
+        storage_id: u32,
+    },
+    ParamTooWide {
+        field: &'static str,
+        value: u64,
+        bits: u8,
+    },
+    ParamTooLarge {
+        field: &'static str,
+        value: u64,
+        max: u64,
+    },
+    // base fee plus the variable fee at the max volatility accumulator, above MAX_FEE
+    FeeTooLarge {
+        fee: Fee1e9,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::ActiveIndexOutOfRange { active_index } => {
+                write!(f, "ErrActiveIndexOutOfRange: {active_index}")
+            }
+            Violation::ActiveBinMissing { active_index } => {
+                write!(f, "ErrActiveBinMissing: {active_index}")
+            }
+            Violation::StorageIdOutOfRange { storage_id } => {
+                write!(f, "ErrStorageIdOutOfRange: {storage_id}")
+            }
+            Violation::WrongSideReserve {
+                storage_id,
+                reserve_x,
+                reserve_y,
+            } => write!(
+                f,
+                "ErrWrongSideReserve: bin {storage_id} holds x {reserve_x}, y {reserve_y}"
+            ),
+            Violation::PriceMismatch {
+                storage_id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "ErrPriceMismatch: bin {storage_id} price {actual}, expected {expected}"
+            ),
+            Violation::LiquidityTooLarge { storage_id } => {
+                write!(f, "ErrMaxLiquidityPerBinExceeded: bin {storage_id}")
+            }
+            Violation::ParamTooWide { field, value, bits } => {
+                write!(f, "ErrParamTooWide: {field} {value} exceeds {bits} bits")
+            }
+            Violation::ParamTooLarge { field, value, max } => {
+                write!(f, "ErrParamTooLarge: {field} {value} exceeds {max}")
+            }
+            Violation::FeeTooLarge { fee } => {
+                write!(f, "ErrFeeTooLarge: {fee} exceeds {}", Fee1e9::MAX)
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl DlmmPair {
+    // Messages of validate(), empty when the snapshot is consistent
+    #[cfg(feature = "wasm")]
+    pub fn validation_errors(&self) -> Vec<String> {
+        match self.validate() {
+            Ok(()) => Vec::new(),
+            Err(violations) => violations.iter().map(Violation::to_string).collect(),
+        }
+    }
+}
+
+impl DlmmPair {
+    // Checks the parameters and every bin, reporting all violations in storage id order
+    pub fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = validate_params(self.params());
+        let bounds = self.params().get_fee_bounds(self.bin_step());
+        if !bounds.is_within_max_fee() {
+            violations.push(Violation::FeeTooLarge {
+                fee: Fee1e9::new(bounds.max_fee()),
+            });
+        };
+
+        // the ids the contract can price for the bin step
+        let max_id = price::get_max_real_id(self.bin_step());
+        let in_range = |storage_id: u32| {
+            storage_id >= price::get_storage_id_from_real_id(-max_id)
+                && storage_id <= price::get_storage_id_from_real_id(max_id)
+        };
+        let active_index = self.active_id();
+        if !in_range(active_index) {
+            violations.push(Violation::ActiveIndexOutOfRange { active_index });
+        };
+        if self.bin(active_index).is_none() {
+            violations.push(Violation::ActiveBinMissing { active_index });
+        };
+
+        let mut bins: Vec<&Bin> = self.bins().collect();
+        bins.sort_by_key(|bin| bin.storage_id);
+        for bin in bins {
+            let storage_id = bin.storage_id;
+            if (storage_id > active_index && bin.reserve_y > 0)
+                || (storage_id < active_index && bin.reserve_x > 0)
+            {
+                violations.push(Violation::WrongSideReserve {
+                    storage_id,
+                    reserve_x: bin.reserve_x,
+                    reserve_y: bin.reserve_y,
+                });
+            };
+
+            if !in_range(storage_id) {
+                violations.push(Violation::StorageIdOutOfRange { storage_id });
+                continue;
+            };
+            let expected = price::get_price_x128_from_storage_id(storage_id, self.bin_step());
+            if bin.price_q128 != expected {
+                violations.push(Violation::PriceMismatch {
+                    storage_id,
+                    expected,
+                    actual: bin.price_q128,
+                });
+            };
+            // with the expected price, the one the swaps use
+            let liquidity_y = U256::from(bin.reserve_y) << constants::SCALE_OFFSET;
+            let liquidity = expected
+                .x128()
+                .checked_mul(U256::from(bin.reserve_x))
+                .and_then(|liquidity_x| liquidity_x.checked_add(liquidity_y));
+            if liquidity.is_none_or(|liquidity| liquidity > constants::MAX_LIQUIDITY_PER_BIN) {
+                violations.push(Violation::LiquidityTooLarge { storage_id });
+            };
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+pub(crate) fn validate_params(params: &DlmmPairParameter) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    // Widths the fields are packed with in the pair parameters word, the Move pair keeps the
+    // layout of the LB v2.1 PairParameterHelper
+    // ref: https://github.com/traderjoe-xyz/joe-v2/blob/main/src/libraries/PairParameterHelper.sol
+    let mut bits = |field: &'static str, value: u64, bits: u8| {
+        if value >> bits != 0 {
+            violations.push(Violation::ParamTooWide { field, value, bits });
+        };
+    };
+    bits("filter_period", params.filter_period as u64, 12);
+    bits("decay_period", params.decay_period as u64, 12);
+    bits("reduction_factor", params.reduction_factor as u64, 14);
+    bits(
+        "variable_fee_control",
+        params.variable_fee_control as u64,
+        24,
+    );
+    bits("protocol_share", params.protocol_share as u64, 14);
+    bits(
+        "max_volatility_accumulator",
+        params.max_volatility_accumulator as u64,
+        20,
+    );
+    bits(
+        "volatility_accumulator",
+        params.volatility_accumulator as u64,
+        20,
+    );
+    bits(
+        "volatility_reference",
+        params.volatility_reference as u64,
+        20,
+    );
+    bits("index_reference", params.index_reference as u64, 24);
+    bits("active_index", params.active_index as u64, 24);
+
+    let mut at_most = |field: &'static str, value: u64, max: u64| {
+        if value > max {
+            violations.push(Violation::ParamTooLarge { field, value, max });
+        };
+    };
+    at_most(
+        "reduction_factor",
+        params.reduction_factor as u64,
+        constants::BASIS_POINT_MAX as u64,
+    );
+    at_most(
+        "protocol_share",
+        params.protocol_share as u64,
+        constants::MAX_PROTOCOL_SHARE as u64,
+    );
+    violations
+}
+
+#[test]
+fn test_valid_pair() {
+    let pair = crate::swap_result::test_one_sided_pair(25, 20);
+    assert!(pair.validate() == Ok(()));
+}
+
+#[test]
+fn test_reports_every_violation() {
+    let mut pair = crate::swap_result::test_one_sided_pair(25, 20);
+    let active_id = pair.active_id();
+    pair.params.filter_period = 1 << 12;
+    pair.params.volatility_accumulator = 1 << 20;
+    pair.params.protocol_share = constants::MAX_PROTOCOL_SHARE + 1;
+    pair.params.variable_fee_control = (1 << 24) - 1;
+
+    let above = pair.bins.get_mut(&(active_id + 1)).unwrap();
+    above.reserve_y = 5;
+    let below = pair.bins.get_mut(&(active_id - 2)).unwrap();
+    below.reserve_x = 6;
+    below.price_q128 = PriceX128::ONE;
+    pair.bins.insert(
+        1,
+        Bin {
+            storage_id: 1,
+            ..Default::default()
+        },
+    );
+    // X at the highest supported price
+    let top = price::get_storage_id_from_real_id(price::get_max_real_id(25));
+    pair.bins.insert(
+        top,
+        Bin {
+            storage_id: top,
+            price_q128: price::get_price_x128_from_storage_id(top, 25),
+            reserve_x: u64::MAX,
+            ..Default::default()
+        },
+    );
+
+    let violations = pair.validate().unwrap_err();
+    let expected = price::get_price_x128_from_storage_id(active_id - 2, 25);
+    assert!(
+        violations
+            == vec![
+                Violation::ParamTooWide {
+                    field: "filter_period",
+                    value: 1 << 12,
+                    bits: 12
+                },
+                Violation::ParamTooWide {
+                    field: "volatility_accumulator",
+                    value: 1 << 20,
+                    bits: 20
+                },
+                Violation::ParamTooLarge {
+                    field: "protocol_share",
+                    value: constants::MAX_PROTOCOL_SHARE as u64 + 1,
+                    max: constants::MAX_PROTOCOL_SHARE as u64
+                },
+                Violation::FeeTooLarge {
+                    fee: Fee1e9::new(pair.params.get_fee_bounds(25).max_fee())
+                },
+                Violation::StorageIdOutOfRange { storage_id: 1 },
+                Violation::WrongSideReserve {
+                    storage_id: active_id - 2,
+                    reserve_x: 6,
+                    reserve_y: 1_000_000
+                },
+                Violation::PriceMismatch {
+                    storage_id: active_id - 2,
+                    expected,
+                    actual: PriceX128::ONE
+                },
+                Violation::WrongSideReserve {
+                    storage_id: active_id + 1,
+                    reserve_x: 1_000_000,
+                    reserve_y: 5
+                },
+                Violation::LiquidityTooLarge { storage_id: top },
+            ]
+    );
+}
+
+#[test]
+fn test_active_index_out_of_range() {
+    let mut pair = crate::swap_result::test_one_sided_pair(25, 1);
+    pair.params.active_index = 1 << 24;
+    let violations = pair.validate().unwrap_err();
+    assert!(violations.contains(&Violation::ActiveIndexOutOfRange {
+        active_index: 1 << 24
+    }));
+    assert!(violations.contains(&Violation::ActiveBinMissing {
+        active_index: 1 << 24
+    }));
+    assert!(violations[0].to_string() == "ErrParamTooWide: active_index 16777216 exceeds 24 bits");
+}
+
+#[test]
+fn test_storage_id_range_bounds() {
+    let mut pair = crate::swap_result::test_one_sided_pair(25, 1);
+    let max_id = price::get_max_real_id(25);
+    for real_id in [-max_id - 1, -max_id, max_id, max_id + 1] {
+        let storage_id = price::get_storage_id_from_real_id(real_id);
+        pair.bins.insert(
+            storage_id,
+            Bin {
+                storage_id,
+                ..Default::default()
+            },
+        );
+    }
+    let out_of_range: Vec<u32> = pair
+        .validate()
+        .unwrap_err()
+        .into_iter()
+        .filter_map(|violation| match violation {
+            Violation::StorageIdOutOfRange { storage_id } => Some(storage_id),
+            _ => None,
+        })
+        .collect();
+    assert!(
+        out_of_range
+            == [
+                price::get_storage_id_from_real_id(-max_id - 1),
+                price::get_storage_id_from_real_id(max_id + 1),
+            ]
+    );
+}
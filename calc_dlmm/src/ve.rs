@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{constants, units::u64_string};
+
+// Vote-escrow lock maths of the voting_escrow Move module, for lock previews. Timestamps are
+// in seconds as the lock start and end. A lock decays linearly to its end: its voting
+// power is amount * (end - t) / MAX_LOCK_TIME, a permanent lock keeps the amount as power.
+// Unlock times are rounded down to a week, so the locks of a week end together and the total
+// supply only changes slope at week boundaries.
+
+pub const WEEK: u64 = 7 * constants::DAY;
+pub const MIN_LOCK_TIME: u64 = WEEK;
+pub const MAX_LOCK_TIME: u64 = 4 * 52 * WEEK;
+
+// The lock fields of the SDK's LockInfo
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VeLock {
+    #[serde(with = "u64_string")]
+    pub amount: u64,
+    // 0 for a permanent lock
+    #[serde(with = "u64_string")]
+    pub end: u64,
+    pub permanent: bool,
+}
+
+// now + the duration, rounded down to a week
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_unlock_time(now: u64, lock_duration_days: u64) -> u64 {
+    let end = lock_duration_days
+        .checked_mul(constants::DAY)
+        .and_then(|duration| now.checked_add(duration))
+        .expect("ErrInvalidLockDuration");
+    end / WEEK * WEEK
+}
+
+// Days to lock from now to end, rounded up, as the SDK turns newLockEndAt into a duration
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn get_lock_duration_days(end: u64, now: u64) -> u64 {
+    end.saturating_sub(now).div_ceil(constants::DAY)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl VeLock {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(amount: u64, lock_duration_days: u64, permanent: bool, now: u64) -> VeLock {
+        assert!(amount > 0, "ErrZeroAmount");
+        if permanent {
+            return VeLock {
+                amount,
+                end: 0,
+                permanent,
+            };
+        }
+        let duration = lock_duration_days.checked_mul(constants::DAY);
+        assert!(
+            duration.is_some_and(|duration| (MIN_LOCK_TIME..=MAX_LOCK_TIME).contains(&duration)),
+            "ErrInvalidLockDuration"
+        );
+        VeLock {
+            amount,
+            end: get_unlock_time(now, lock_duration_days),
+            permanent,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        !self.permanent && now >= self.end
+    }
+
+    pub fn voting_power(&self, now: u64) -> u64 {
+        if self.permanent {
+            self.amount
+        } else if self.is_expired(now) {
+            0
+        } else {
+            (self.amount as u128 * (self.end - now) as u128 / MAX_LOCK_TIME as u128) as u64
+        }
+    }
+
+    pub fn increase_amount(&mut self, amount: u64, now: u64) {
+        assert!(amount > 0, "ErrZeroAmount");
+        assert!(!self.is_expired(now), "ErrLockExpired");
+        self.amount = self.amount.checked_add(amount).expect("ErrAmountOverflow");
+    }
+
+    // The new unlock time must be later than the current one and within MAX_LOCK_TIME
+    pub fn increase_unlock_time(&mut self, lock_duration_days: u64, now: u64) {
+        assert!(!self.permanent, "ErrPermanentLock");
+        assert!(!self.is_expired(now), "ErrLockExpired");
+        let end = get_unlock_time(now, lock_duration_days);
+        assert!(end > self.end, "ErrLockDurationNotIncreased");
+        assert!(
+            now.checked_add(MAX_LOCK_TIME)
+                .is_some_and(|max_end| end <= max_end),
+            "ErrInvalidLockDuration"
+        );
+        self.end = end;
+    }
+
+    pub fn lock_permanent(&mut self, now: u64) {
+        assert!(!self.permanent, "ErrPermanentLock");
+        assert!(!self.is_expired(now), "ErrLockExpired");
+        self.permanent = true;
+        self.end = 0;
+    }
+
+    // Back to a lock decaying from MAX_LOCK_TIME
+    pub fn unlock_permanent(&mut self, now: u64) {
+        assert!(self.permanent, "ErrNotPermanentLock");
+        self.permanent = false;
+        self.end = get_unlock_time(now, MAX_LOCK_TIME / constants::DAY);
+    }
+
+    // Lock resulting from merging this lock into `to`: the amounts add up, the later end is
+    // kept and a permanent `to` stays permanent
+    pub fn merge(&self, to: &VeLock, now: u64) -> VeLock {
+        assert!(!self.permanent, "ErrPermanentLock");
+        assert!(
+            !self.is_expired(now) && !to.is_expired(now),
+            "ErrLockExpired"
+        );
+        VeLock {
+            amount: self
+                .amount
+                .checked_add(to.amount)
+                .expect("ErrAmountOverflow"),
+            end: if to.permanent {
+                0
+            } else {
+                self.end.max(to.end)
+            },
+            permanent: to.permanent,
+        }
+    }
+}
+
+// Total voting power of a set of locks from a point in time on, as the bias and slope of
+// the decaying locks and the slope changes at their unlock times. The bias is kept in
+// amount * seconds, so the total is rounded once and can exceed the sum of the lock powers
+// by less than one unit per lock.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VeSupply {
+    ts: u64,
+    permanent: u128,
+    // sum of amount * (end - ts) of the decaying locks
+    bias: u128,
+    // sum of the decaying amounts
+    slope: u128,
+    // decaying amount ending at each unlock time
+    slope_changes: BTreeMap<u64, u128>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl VeSupply {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(now: u64) -> VeSupply {
+        VeSupply {
+            ts: now,
+            ..Default::default()
+        }
+    }
+
+    // Locks as a JSON list of VeLock
+    #[cfg(feature = "wasm")]
+    pub fn from_locks_json(json: &str, now: u64) -> Result<VeSupply, JsError> {
+        let locks: Vec<VeLock> = serde_json::from_str(json)?;
+        Ok(VeSupply::from_locks(&locks, now))
+    }
+
+    // Expired locks add nothing
+    pub fn add_lock(&mut self, lock: &VeLock) {
+        if lock.permanent {
+            self.permanent += lock.amount as u128;
+        } else if !lock.is_expired(self.ts) {
+            self.bias += lock.amount as u128 * (lock.end - self.ts) as u128;
+            self.slope += lock.amount as u128;
+            *self.slope_changes.entry(lock.end).or_default() += lock.amount as u128;
+        }
+    }
+
+    pub fn total_voting_power(&self, now: u64) -> u128 {
+        assert!(now >= self.ts, "ErrTimestampBeforeSupply");
+        let (mut bias, mut slope, mut last) = (self.bias, self.slope, self.ts);
+        for (&end, &change) in self.slope_changes.range(..=now) {
+            bias -= slope * (end - last) as u128;
+            slope -= change;
+            last = end;
+        }
+        bias -= slope * (now - last) as u128;
+        self.permanent + bias / MAX_LOCK_TIME as u128
+    }
+
+    // Total voting power at each of the next `weeks` week boundaries
+    #[cfg(feature = "wasm")]
+    pub fn supply_curve_json(&self, weeks: u32) -> String {
+        let curve: Vec<String> = self
+            .supply_curve(weeks)
+            .into_iter()
+            .map(|(_, power)| power.to_string())
+            .collect();
+        serde_json::to_string(&curve).unwrap()
+    }
+}
+
+impl VeSupply {
+    pub fn from_locks(locks: &[VeLock], now: u64) -> VeSupply {
+        let mut supply = VeSupply::new(now);
+        for lock in locks {
+            supply.add_lock(lock);
+        }
+        supply
+    }
+
+    // (timestamp, total voting power) at each of the next `weeks` week boundaries
+    pub fn supply_curve(&self, weeks: u32) -> Vec<(u64, u128)> {
+        let first = self.ts / WEEK * WEEK + WEEK;
+        (0..weeks as u64)
+            .map(|week| {
+                let ts = first + week * WEEK;
+                (ts, self.total_voting_power(ts))
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_lock_voting_power() {
+    let now = 100 * WEEK + 3 * constants::DAY;
+    // 4 years from mid-week end at the start of the week
+    let lock = VeLock::new(1_000_000_000, 4 * 52 * 7, false, now);
+    assert!(lock.end == 308 * WEEK);
+    // 1453 of 1456 days left
+    assert!(lock.voting_power(now) == 997_939_560);
+    assert!(lock.voting_power(lock.end - MAX_LOCK_TIME / 2) == 500_000_000);
+    assert!(lock.voting_power(lock.end) == 0 && lock.is_expired(lock.end));
+
+    let permanent = VeLock::new(1_000_000_000, 0, true, now);
+    assert!(permanent.voting_power(u64::MAX) == 1_000_000_000);
+    assert!(get_lock_duration_days(now + constants::DAY + 1, now) == 2);
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidLockDuration")]
+fn test_lock_too_short() {
+    VeLock::new(1_000, 6, false, 0);
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidLockDuration")]
+fn test_lock_duration_overflow() {
+    VeLock::new(1_000, u64::MAX / constants::DAY + 1, false, 0);
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidLockDuration")]
+fn test_unlock_time_overflow() {
+    get_unlock_time(u64::MAX - constants::DAY + 1, 1);
+}
+
+#[test]
+#[should_panic(expected = "ErrInvalidLockDuration")]
+fn test_increase_unlock_time_overflow() {
+    // the new end fits, now + MAX_LOCK_TIME doesn't
+    let now = u64::MAX - 4 * WEEK;
+    let mut lock = VeLock::new(1_000, 7, false, now);
+    lock.increase_unlock_time(14, now);
+}
+
+#[test]
+fn test_lock_updates() {
+    let now = 100 * WEEK;
+    let mut lock = VeLock::new(1_000, 14, false, now);
+    lock.increase_amount(500, now);
+    lock.increase_unlock_time(28, now + constants::DAY);
+    assert!(lock.amount == 1_500 && lock.end == 104 * WEEK);
+
+    let other = VeLock::new(2_000, 7, false, now);
+    let merged = other.merge(&lock, now);
+    assert!(merged.amount == 3_500 && merged.end == lock.end && !merged.permanent);
+    assert!(merged.voting_power(now) >= lock.voting_power(now) + other.voting_power(now));
+
+    lock.lock_permanent(now);
+    assert!(other.merge(&lock, now).permanent && lock.voting_power(now) == 1_500);
+    lock.unlock_permanent(now);
+    assert!(lock.end == now + MAX_LOCK_TIME && !lock.permanent);
+}
+
+#[test]
+#[should_panic(expected = "ErrLockDurationNotIncreased")]
+fn test_lock_unlock_time_not_increased() {
+    let mut lock = VeLock::new(1_000, 28, false, 0);
+    lock.increase_unlock_time(21, 0);
+}
+
+#[test]
+fn test_supply_curve() {
+    let now = 50 * WEEK + 1;
+    let locks = [
+        VeLock::new(1_000_000, 7, false, now),
+        VeLock::new(3_000_000, 365, false, now),
+        VeLock::new(2_000_000, 4 * 52 * 7, false, now),
+        VeLock::new(500_000, 0, true, now),
+        // expired
+        VeLock {
+            amount: 9_000_000,
+            end: 50 * WEEK,
+            permanent: false,
+        },
+    ];
+    let supply = VeSupply::from_locks(&locks, now);
+    let sum = |ts: u64| -> u128 { locks.iter().map(|l| l.voting_power(ts) as u128).sum() };
+    for ts in [
+        now,
+        now + WEEK,
+        now + 30 * WEEK,
+        now + 100 * WEEK,
+        now + 300 * WEEK,
+    ] {
+        let total = supply.total_voting_power(ts);
+        assert!(total >= sum(ts) && total - sum(ts) < locks.len() as u128);
+    }
+    let curve = supply.supply_curve(210);
+    assert!(curve[0].0 == 51 * WEEK && curve.windows(2).all(|w| w[1].1 <= w[0].1));
+    // only the permanent lock is left
+    assert!(curve.last().unwrap().1 == 500_000);
+}